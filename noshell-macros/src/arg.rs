@@ -1,33 +1,110 @@
 //! Argument parsing and manipulation.
 
 use proc_macro2::Span;
-use syn::{Field, Ident, Type, spanned::Spanned};
+use quote::format_ident;
+use syn::{
+    Attribute, Expr, ExprLit, Field, Ident, Index, Lit, Member, Meta, Type, spanned::Spanned,
+};
 
-use crate::attr::Attr;
+use crate::attr::{Attr, AttrKind, AttrName, AttrValue};
+use crate::ty::Ty;
 
 /// Argument metadata.
 pub struct MetaArg {
     /// Argument span.
     pub span: Span,
 
-    /// Argument identifier (i.e. field identifier).
+    /// Argument identifier (i.e. field identifier, or `_<index>` for tuple struct fields).
     pub id: Ident,
 
+    /// Field member, either named or unnamed.
+    pub member: Member,
+
     /// Type.
     pub ty: Type,
 
+    /// Kind of type, either told from the type itself or given by `#[arg(kind = "...")]`.
+    pub kind: Ty,
+
+    /// Documentation of the field, with its lines joined.
+    pub doc: String,
+
     /// Attributes.
     pub attrs: Vec<Attr>,
 }
 
 impl MetaArg {
-    /// Create a new argument metadata.
-    pub fn new(field: &Field, attrs: Vec<Attr>) -> Self {
-        MetaArg {
+    /// Create a new argument metadata, given the field index in its struct.
+    pub fn new(field: &Field, index: usize, attrs: Vec<Attr>) -> syn::Result<Self> {
+        let (id, member) = match &field.ident {
+            Some(id) => (id.clone(), Member::Named(id.clone())),
+            None => (
+                format_ident!("_{}", index),
+                Member::Unnamed(Index {
+                    index: index as u32,
+                    span: field.span(),
+                }),
+            ),
+        };
+
+        let kind = match attrs
+            .iter()
+            .find(|x| x.kind == AttrKind::Arg && x.name == Some(AttrName::Kind))
+        {
+            Some(attr) => parse_kind(attr)?,
+            None => Ty::from_syn_ty(&field.ty),
+        };
+
+        Ok(MetaArg {
             span: field.span(),
-            id: field.ident.clone().unwrap(),
+            id,
+            member,
             ty: field.ty.clone(),
+            kind,
+            doc: parse_doc(&field.attrs),
             attrs,
-        }
+        })
+    }
+
+    /// Check if the argument is positional (i.e. a tuple struct field).
+    pub fn is_positional(&self) -> bool {
+        matches!(self.member, Member::Unnamed(_))
     }
 }
+
+/// Join the lines of the documentation of a field or a struct (i.e. `///` comments), as a single
+/// paragraph.
+pub fn parse_doc(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|x| x.path().is_ident("doc"))
+        .filter_map(|x| match &x.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_kind(attr: &Attr) -> syn::Result<Ty> {
+    let Some(AttrValue::LitStr(lit)) = &attr.value else {
+        return Err(syn::Error::new(
+            attr.id.span(),
+            "expected `kind` to be a literal string",
+        ));
+    };
+
+    Ty::from_name(&lit.value()).ok_or_else(|| {
+        syn::Error::new(
+            lit.span(),
+            "expected `kind` to be one of \"simple\", \"option\", \"option_option\", \"vec\", \"option_vec\", \"array\", \"option_array\", \"map\" or \"option_map\"",
+        )
+    })
+}
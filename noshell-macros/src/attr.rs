@@ -6,7 +6,9 @@
 use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::{Attribute, Expr, Ident, LitStr, Token};
+use syn::{Attribute, Expr, ExprLit, Ident, Lit, LitStr, Token};
+
+use crate::errors::Errors;
 
 /// Attribute name.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -23,14 +25,141 @@ pub enum AttrKind {
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum AttrName {
+    /// Accept unambiguous prefixes of long flags.
+    Abbrev,
+
+    /// Additional long flag.
+    Alias,
+
+    /// Path of the noshell crate.
+    Crate,
+
+    /// Value used when the flag is given without any value.
+    DefaultMissingValue,
+
+    /// Argument which is still parsed, but reported as deprecated with a note.
+    Deprecated,
+
+    /// Function called with the identifier and the note of each deprecated argument given.
+    DeprecationHook,
+
+    /// Argument which is left out of the help and the completions.
+    Hide,
+
+    /// Kind of the field type, when it cannot be told from the type itself (e.g. an alias).
+    Kind,
+
     /// Limit number of values.
     Limit,
 
     /// Long flag.
     Long,
 
+    /// Stop parsing flags at the first positional argument.
+    OptionsFirst,
+
+    /// Path of the parser crate, used without noshell.
+    ParserCrate,
+
+    /// Raw arguments, which collect undefined flags and their values.
+    Raw,
+
+    /// Rule applied to field identifiers to build their default long flag.
+    RenameAll,
+
+    /// Values can only be attached to the flag with an equal sign.
+    RequireEquals,
+
+    /// Separator of key-value pairs.
+    Separator,
+
+    /// Value which should not be echoed when asked for interactively.
+    Secret,
+
+    /// Key of the setting which gives the value of a missing argument.
+    Setting,
+
     /// Short flag.
     Short,
+
+    /// Additional short flag.
+    ShortAlias,
+
+    /// Field which is not an argument, initialized with its default value or an expression.
+    Skip,
+
+    /// Generate `to_args`, which writes the arguments back to a command line.
+    ToArgs,
+
+    /// Trailing var args, which capture all the remaining words verbatim.
+    TrailingVarArg,
+
+    /// Policy on undefined flags.
+    Undefined,
+
+    /// Generate `try_update_from`, which only changes the arguments given on a command line.
+    Update,
+
+    /// Delimiter of the values within a single word.
+    ValueDelimiter,
+
+    /// Values given by name, whose type implements `ValueEnum`.
+    ValueEnum,
+}
+
+impl AttrName {
+    /// Check if the name is valid for the given attribute kind.
+    pub fn is_valid_for(self, kind: AttrKind) -> bool {
+        match self {
+            AttrName::Limit => true,
+
+            AttrName::Abbrev
+            | AttrName::Crate
+            | AttrName::DeprecationHook
+            | AttrName::OptionsFirst
+            | AttrName::ParserCrate
+            | AttrName::RenameAll
+            | AttrName::ToArgs
+            | AttrName::Undefined
+            | AttrName::Update => kind == AttrKind::NoShell,
+
+            AttrName::Alias
+            | AttrName::DefaultMissingValue
+            | AttrName::Deprecated
+            | AttrName::Hide
+            | AttrName::Kind
+            | AttrName::Long
+            | AttrName::Raw
+            | AttrName::RequireEquals
+            | AttrName::Secret
+            | AttrName::Separator
+            | AttrName::Setting
+            | AttrName::Short
+            | AttrName::ShortAlias
+            | AttrName::Skip
+            | AttrName::TrailingVarArg
+            | AttrName::ValueDelimiter
+            | AttrName::ValueEnum => kind == AttrKind::Arg,
+        }
+    }
+
+    /// Check if the attribute is a switch, which is turned on by its name alone (e.g. `hide`), or
+    /// by a boolean literal (e.g. `hide = false`).
+    pub fn is_switch(self) -> bool {
+        matches!(
+            self,
+            AttrName::Abbrev
+                | AttrName::Hide
+                | AttrName::OptionsFirst
+                | AttrName::Raw
+                | AttrName::RequireEquals
+                | AttrName::Secret
+                | AttrName::ToArgs
+                | AttrName::TrailingVarArg
+                | AttrName::Update
+                | AttrName::ValueEnum
+        )
+    }
 }
 
 /// Attribute value.
@@ -60,9 +189,11 @@ pub struct Attr {
 }
 
 impl Attr {
-    /// Parse all attributes given as input.
+    /// Parse all attributes given as input. All the errors are reported at once, including the
+    /// unknown names and the names given to the wrong attribute kind.
     pub fn parse_all(attrs: &[Attribute]) -> Result<Vec<Self>, syn::Error> {
         let mut parsed = Vec::new();
+        let mut errors = Errors::new();
 
         for attr in attrs {
             let kind = if attr.path().is_ident("noshell") {
@@ -73,28 +204,93 @@ impl Attr {
                 continue;
             };
 
-            let args = attr.parse_args_with(Punctuated::<Self, Token![,]>::parse_terminated)?;
+            let Some(args) =
+                errors.check(attr.parse_args_with(Punctuated::<Self, Token![,]>::parse_terminated))
+            else {
+                continue;
+            };
 
             // Flatten all the attributes and keep their individual kind for further processing.
             for mut arg in args {
                 arg.kind = kind;
-                parsed.push(arg);
+
+                match arg.name {
+                    // A switch turned off is left out, as if it was not given.
+                    Some(name) if name.is_valid_for(kind) && name.is_switch() => {
+                        if let Some(true) = errors.check(arg.switch_state()) {
+                            arg.value = None;
+                            parsed.push(arg);
+                        }
+                    }
+                    Some(name) if name.is_valid_for(kind) => parsed.push(arg),
+                    _ => errors.push(syn::Error::new(
+                        arg.id.span(),
+                        format!(
+                            "unknown attribute `{}` in `{}`",
+                            arg.id,
+                            attr.path().get_ident().unwrap()
+                        ),
+                    )),
+                }
             }
         }
 
-        Ok(parsed)
+        errors.finish().map(|_| parsed)
+    }
+
+    /// Get the state of a switch, which is on if given without any value.
+    fn switch_state(&self) -> Result<bool, syn::Error> {
+        match &self.value {
+            None => Ok(true),
+            Some(AttrValue::Expr(Expr::Lit(ExprLit {
+                lit: Lit::Bool(value),
+                ..
+            }))) => Ok(value.value),
+            Some(_) => Err(syn::Error::new(
+                self.id.span(),
+                format!(
+                    "expected `{}`, `{} = true` or `{} = false`",
+                    self.id, self.id, self.id
+                ),
+            )),
+        }
     }
 }
 
 impl Parse for Attr {
     fn parse(input: ParseStream<'_>) -> Result<Self, syn::Error> {
-        let id: Ident = input.parse()?;
+        // Keywords are valid names (e.g. `crate`).
+        let id = input.call(Ident::parse_any)?;
         let name = id.unraw().to_string();
 
         let name = match name.as_str() {
+            "abbrev" => Some(AttrName::Abbrev),
+            "alias" => Some(AttrName::Alias),
+            "crate" => Some(AttrName::Crate),
+            "default_missing_value" => Some(AttrName::DefaultMissingValue),
+            "deprecated" => Some(AttrName::Deprecated),
+            "deprecation_hook" => Some(AttrName::DeprecationHook),
+            "hide" => Some(AttrName::Hide),
+            "kind" => Some(AttrName::Kind),
             "limit" => Some(AttrName::Limit),
             "long" => Some(AttrName::Long),
+            "options_first" => Some(AttrName::OptionsFirst),
+            "parser_crate" => Some(AttrName::ParserCrate),
+            "raw" => Some(AttrName::Raw),
+            "rename_all" => Some(AttrName::RenameAll),
+            "require_equals" => Some(AttrName::RequireEquals),
+            "secret" => Some(AttrName::Secret),
+            "separator" => Some(AttrName::Separator),
+            "setting" => Some(AttrName::Setting),
             "short" => Some(AttrName::Short),
+            "short_alias" => Some(AttrName::ShortAlias),
+            "skip" => Some(AttrName::Skip),
+            "to_args" => Some(AttrName::ToArgs),
+            "trailing_var_arg" => Some(AttrName::TrailingVarArg),
+            "undefined" => Some(AttrName::Undefined),
+            "update" => Some(AttrName::Update),
+            "value_delimiter" => Some(AttrName::ValueDelimiter),
+            "value_enum" => Some(AttrName::ValueEnum),
             _ => None,
         };
 
@@ -272,4 +468,141 @@ mod tests {
             attr.value
         );
     }
+
+    #[test]
+    fn it_should_parse_arg_with_raw() {
+        let attr: Attribute = syn::parse_quote!(#[arg(raw)]);
+
+        let res = Attr::parse_all(&[attr]);
+        assert!(res.is_ok());
+
+        let attrs = res.unwrap();
+        assert_eq!(1, attrs.len());
+
+        let attr = attrs.first().unwrap();
+        assert_eq!(AttrKind::Arg, attr.kind);
+        assert_eq!(Some(AttrName::Raw), attr.name);
+        assert_eq!(None, attr.value);
+    }
+
+    #[test]
+    fn it_should_parse_noshell_with_undefined_policy() {
+        let attr: Attribute = syn::parse_quote!(#[noshell(undefined = "ignore")]);
+
+        let res = Attr::parse_all(&[attr]);
+        assert!(res.is_ok());
+
+        let attrs = res.unwrap();
+        assert_eq!(1, attrs.len());
+
+        let attr = attrs.first().unwrap();
+        assert_eq!(AttrKind::NoShell, attr.kind);
+        assert_eq!(Some(AttrName::Undefined), attr.name);
+        assert_eq!(
+            Some(AttrValue::LitStr(syn::parse_quote!("ignore"))),
+            attr.value
+        );
+    }
+
+    #[test]
+    fn it_should_parse_noshell_with_abbrev() {
+        let attr: Attribute = syn::parse_quote!(#[noshell(abbrev)]);
+
+        let res = Attr::parse_all(&[attr]);
+        assert!(res.is_ok());
+
+        let attrs = res.unwrap();
+        assert_eq!(1, attrs.len());
+
+        let attr = attrs.first().unwrap();
+        assert_eq!(AttrKind::NoShell, attr.kind);
+        assert_eq!(Some(AttrName::Abbrev), attr.name);
+        assert_eq!(None, attr.value);
+    }
+
+    #[test]
+    fn it_should_parse_switches_with_bool_literals() {
+        let attrs: [Attribute; 2] = [
+            syn::parse_quote!(#[noshell(abbrev = false, options_first = true)]),
+            syn::parse_quote!(#[arg(hide = false, secret)]),
+        ];
+
+        let attrs = Attr::parse_all(&attrs).unwrap();
+
+        let names = attrs.iter().map(|x| x.name).collect::<Vec<_>>();
+        assert_eq!(
+            vec![Some(AttrName::OptionsFirst), Some(AttrName::Secret)],
+            names
+        );
+        assert!(attrs.iter().all(|x| x.value.is_none()));
+    }
+
+    #[test]
+    fn it_should_reject_switches_with_other_values() {
+        let attr: Attribute = syn::parse_quote!(#[arg(raw = "yes", trailing_var_arg = 1)]);
+
+        let err = Attr::parse_all(&[attr]).unwrap_err();
+
+        assert_eq!(2, err.into_iter().count());
+    }
+
+    #[test]
+    fn it_should_parse_arg_with_require_equals_and_default_missing_value() {
+        let attr: Attribute =
+            syn::parse_quote!(#[arg(require_equals, default_missing_value = "auto")]);
+
+        let res = Attr::parse_all(&[attr]);
+        assert!(res.is_ok());
+
+        let attrs = res.unwrap();
+        assert_eq!(2, attrs.len());
+
+        assert_eq!(AttrKind::Arg, attrs[0].kind);
+        assert_eq!(Some(AttrName::RequireEquals), attrs[0].name);
+        assert_eq!(None, attrs[0].value);
+
+        assert_eq!(AttrKind::Arg, attrs[1].kind);
+        assert_eq!(Some(AttrName::DefaultMissingValue), attrs[1].name);
+        assert_eq!(
+            Some(AttrValue::LitStr(syn::parse_quote!("auto"))),
+            attrs[1].value
+        );
+    }
+
+    #[test]
+    fn it_should_parse_noshell_with_crate_path() {
+        let attr: Attribute = syn::parse_quote!(#[noshell(crate = "platform::shell")]);
+
+        let res = Attr::parse_all(&[attr]);
+        assert!(res.is_ok());
+
+        let attrs = res.unwrap();
+        assert_eq!(1, attrs.len());
+
+        assert_eq!(AttrKind::NoShell, attrs[0].kind);
+        assert_eq!(Some(AttrName::Crate), attrs[0].name);
+        assert_eq!(
+            Some(AttrValue::LitStr(syn::parse_quote!("platform::shell"))),
+            attrs[0].value
+        );
+    }
+
+    #[test]
+    fn it_should_reject_unknown_and_misplaced_names() {
+        let attrs: [Attribute; 2] = [
+            syn::parse_quote!(#[arg(shrot, long)]),
+            syn::parse_quote!(#[noshell(short)]),
+        ];
+
+        let err = Attr::parse_all(&attrs).unwrap_err();
+        let messages = err.into_iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                "unknown attribute `shrot` in `arg`",
+                "unknown attribute `short` in `noshell`"
+            ],
+            messages
+        );
+    }
 }
@@ -2,98 +2,478 @@
 
 use std::collections::HashSet;
 
-use proc_macro_error::abort_call_site;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, quote_spanned};
 use syn::ext::IdentExt;
 use syn::{
-    Data, DataStruct, DeriveInput, Expr, ExprLit, Fields, FieldsNamed, Lit, LitStr,
+    Data, DataStruct, DeriveInput, Expr, ExprLit, ExprPath, Fields, Lit, LitStr, Path,
     spanned::Spanned,
 };
-use syn::{Ident, Type};
+use syn::{Generics, Ident, Type};
 
-use crate::arg::MetaArg;
+use crate::arg::{MetaArg, parse_doc};
 use crate::attr::{Attr, AttrKind, AttrName, AttrValue};
+use crate::errors::Errors;
+use crate::rename::RenameRule;
 use crate::ty::{Ty, get_inner_ty};
 
 pub fn run(item: TokenStream) -> TokenStream {
     let input: DeriveInput = match syn::parse2(item) {
         Ok(x) => x,
-        Err(err) => abort_call_site!("failed to parse input, {}", err),
+        Err(err) => return err.to_compile_error(),
     };
 
-    match try_run(&input) {
+    // The paths are only invalid if the derive fails, in which case the default ones are used by
+    // the placeholder methods.
+    let paths = Attr::parse_all(&input.attrs)
+        .and_then(|x| build_paths(&x))
+        .unwrap_or_default();
+
+    // All the errors are reported at their own span, and the methods are still defined, so that
+    // their uses do not add more errors.
+    let derived = match try_run(&input) {
         Ok(derived) => derived,
-        Err(err) => abort_call_site!("failed to derive input, {}", err),
+        Err(err) => {
+            let error = err.to_compile_error();
+            let placeholder = build_placeholder(&input, &paths);
+            quote! { #error #placeholder }
+        }
+    };
+
+    // The process arguments are dropped after parsing, so nothing can be borrowed from them.
+    // They are only available from noshell, not from the parser alone.
+    if let Some(env) = &paths.env
+        && cfg!(feature = "std")
+        && input.generics.params.is_empty()
+    {
+        let std = build_std_parser(&input.ident, env, &paths.parser, &paths.error);
+        quote! { #derived #std }
+    } else {
+        derived
+    }
+}
+
+/// Paths to the items used by the generated code.
+pub struct Paths {
+    /// The parser crate (e.g. `noshell::parser`).
+    pub parser: TokenStream,
+
+    /// The error type returned by the parsers.
+    pub error: TokenStream,
+
+    /// The module to parse the process arguments, if any (e.g. `noshell::env`).
+    pub env: Option<TokenStream>,
+}
+
+impl Default for Paths {
+    fn default() -> Self {
+        Paths {
+            parser: quote!(noshell::parser),
+            error: quote!(noshell::Error),
+            env: Some(quote!(noshell::env)),
+        }
+    }
+}
+
+/// Build the paths from `#[noshell(crate = "...")]`, which gives the path of noshell (e.g. when
+/// re-exported by another crate), or from `#[noshell(parser_crate = "...")]`, which gives the
+/// path of the parser crate used alone, with its own error type.
+pub fn build_paths(attrs: &[Attr]) -> syn::Result<Paths> {
+    let krate = find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::Crate)
+    });
+
+    let parser_crate = find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::ParserCrate)
+    });
+
+    match (krate, parser_crate) {
+        (Some(_), Some(attr)) => Err(syn::Error::new(
+            attr.id.span(),
+            "cannot use both `crate` and `parser_crate`",
+        )),
+
+        (Some(attr), None) => {
+            let path = parse_attr_path_value(attr)?;
+            Ok(Paths {
+                parser: quote!(#path::parser),
+                error: quote!(#path::Error),
+                env: Some(quote!(#path::env)),
+            })
+        }
+
+        (None, Some(attr)) => {
+            let path = parse_attr_path_value(attr)?;
+            Ok(Paths {
+                parser: quote!(#path),
+                error: quote!(#path::Error),
+                env: None,
+            })
+        }
+
+        (None, None) => Ok(Paths::default()),
+    }
+}
+
+fn parse_attr_path_value(attr: &Attr) -> syn::Result<Path> {
+    match &attr.value {
+        Some(AttrValue::LitStr(lit)) => lit.parse(),
+        Some(AttrValue::Expr(Expr::Path(ExprPath { path, .. }))) => Ok(path.clone()),
+        _ => Err(syn::Error::new(
+            attr.id.span(),
+            format!("expected `{}` to be a path", attr.id),
+        )),
     }
 }
 
 // This is the default value.
 const PARSED_ARGS_DEFAULT_CAPACITY: usize = 32;
 
+const DEFAULT_KEY_VALUE_SEPARATOR: char = '=';
+
 pub fn try_run(input: &DeriveInput) -> syn::Result<TokenStream> {
     let ident = &input.ident;
 
     match input.data {
-        Data::Struct(DataStruct {
-            fields: Fields::Named(ref fields),
-            ..
-        }) => {
-            let args = collect_args_meta(fields)?;
-            let init = build_args_init(&args, format_ident!("args"))?;
+        // Named fields are flags, tuple fields are positional arguments, and unit structs have no
+        // arguments.
+        Data::Struct(DataStruct { ref fields, .. }) => {
+            // Each step is checked even if a previous one failed, so that all the errors are
+            // reported at once.
+            let mut errors = Errors::new();
+
+            let attrs = errors.check_or_default(Attr::parse_all(&input.attrs));
+            let Paths { parser, error, .. } = errors.check_or_default(build_paths(&attrs));
+
+            let args = collect_args_meta(fields, &mut errors);
+            let init =
+                errors.check_or_default(build_args_init(&args, format_ident!("args"), &parser));
+
+            let rename = errors.check_or_default(build_rename_rule(&attrs));
+            let lookup = errors.check_or_default(build_arg_lookup_table(&args, rename, &parser));
 
-            let lookup = build_arg_lookup_table(&args)?;
+            let size = errors
+                .check_or_default(get_noshell_attr_limit_arg_value(&attrs))
+                .unwrap_or(PARSED_ARGS_DEFAULT_CAPACITY);
+            let undefined = errors.check_or_default(build_undefined_policy(&attrs, &args, &parser));
 
-            let attrs = Attr::parse_all(&input.attrs)?;
-            let size =
-                get_noshell_attr_limit_arg_value(&attrs)?.unwrap_or(PARSED_ARGS_DEFAULT_CAPACITY);
+            let trailing = errors.check_or_default(build_trailing(&args));
+            let matching = build_flag_matching(&attrs, &parser);
+            let mode = build_parsing_mode(&attrs, &parser);
+            let options = errors.check_or_default(build_arg_options(&args, &parser));
+            let hook = errors.check_or_default(build_deprecation_hook(&attrs));
+
+            errors.finish()?;
+
+            // The writer parses the same attributes as the steps above, so it is only built once
+            // they succeed, not to report their errors twice.
+            let to_args = if has_to_args_attr(&attrs) {
+                build_to_args(&args, rename, &parser)?
+            } else {
+                TokenStream::new()
+            };
+            let info = build_args_info(&args, rename, &parser)?;
+            let about = parse_doc(&input.attrs);
+
+            let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+            let InputGenerics {
+                generics,
+                word,
+                provider,
+                bounds,
+            } = build_input_generics(&input.generics, &parser);
+
+            let ids = args
+                .iter()
+                .filter(|x| is_flag_arg(x) && !is_raw_arg(x))
+                .map(|x| x.id.unraw().to_string())
+                .collect::<Vec<_>>();
+            let count = ids.len();
+
+            // The parsed arguments are still checked, even if no field is an argument.
+            let unused = if args.iter().all(is_skipped_arg) {
+                quote!(let _ = args;)
+            } else {
+                TokenStream::new()
+            };
+
+            // The updated fields are compared to their new value, so that their types must
+            // implement `PartialEq`, which is only required with `#[noshell(update)]`.
+            let update = if has_update_attr(&attrs) {
+                let updated = args
+                    .iter()
+                    .filter(|x| !is_skipped_arg(x) && !is_raw_arg(x))
+                    .count();
+                let update = build_args_update(&args, format_ident!("args"), &parser)?;
+
+                quote! {
+                    pub fn try_update_from #generics (
+                        &mut self,
+                        input: I,
+                    ) -> Result<#parser::ChangedArgs<'static, #updated>, #error>
+                    where
+                        I: IntoIterator,
+                        I::Item: #parser::IntoWord<#word>,
+                        #bounds
+                    {
+                        let args = #parser::ParsedArgs::<'_, #size, #word>::try_parse_from(
+                            input,
+                            Self::lookup_table(),
+                        )?;
+
+                        #update
+                    }
+                }
+            } else {
+                TokenStream::new()
+            };
+
+            let with_generics = match input.generics.lifetimes().next() {
+                Some(_) => quote!(<I, P>),
+                None => quote!(<I, W, P>),
+            };
 
             Ok(quote! {
-                impl #ident {
-                    pub fn try_parse_from<'a>(input: &'a [&'a str]) -> Result<Self, noshell::Error>
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    pub const ABOUT: &'static str = #about;
+
+                    pub const ARGS: &'static [#parser::ArgInfo<'static>] = &#info;
+
+                    pub fn lookup_table() -> &'static #parser::ArgLookupTable<'static> {
+                        use #parser::ArgLookupTable;
+
+                        static LOOKUP_TABLE: ArgLookupTable<'_> =
+                            ArgLookupTable::new(&#lookup)#undefined #trailing #matching #mode #options #hook
+                                .with_sorted();
+                        &LOOKUP_TABLE
+                    }
+
+                    pub fn try_parse_from #generics (input: I) -> Result<Self, #error>
+                    where
+                        I: IntoIterator,
+                        I::Item: #parser::IntoWord<#word>,
+                        #bounds
+                    {
+                        Self::try_parse_with(input, &()).map(|(parsed, _)| parsed)
+                    }
+
+                    pub fn try_parse_with #with_generics (
+                        input: I,
+                        provider: #provider,
+                    ) -> Result<(Self, #parser::ValueSources<'static, #count>), #error>
+                    where
+                        I: IntoIterator,
+                        I::Item: #parser::IntoWord<#word>,
+                        P: #parser::ValueProvider + ?Sized,
+                        #bounds
                     {
-                        use noshell::parser::{ArgLookupTable, ParsedArgs};
+                        use #parser::{ParsedArgs, ValueSources};
 
-                        static LOOKUP_TABLE: ArgLookupTable<'_> = ArgLookupTable::new(&#lookup);
-                        let args = ParsedArgs::<'_, #size>::try_parse_from(input, &LOOKUP_TABLE)?;
+                        let args = ParsedArgs::<'_, #size, #word>::try_parse_with(
+                            input,
+                            Self::lookup_table(),
+                            provider,
+                        )?;
 
-                        Ok(#ident #init)
+                        let sources = ValueSources::new([#((#ids, args.source_of(#ids))),*]);
+                        #unused
+
+                        Ok((#ident #init, sources))
                     }
 
-                    pub fn parse_from<'a>(iter: &'a [&'a str]) -> Self {
+                    #update
+
+                    pub fn parse_from #generics (iter: I) -> Self
+                    where
+                        I: IntoIterator,
+                        I::Item: #parser::IntoWord<#word>,
+                        #bounds
+                    {
                         Self::try_parse_from(iter).expect("should parse arguments from iterator")
                     }
+
+                    #to_args
                 }
             })
         }
 
-        // FIXME: do not support unamed struct or enum.
+        // FIXME: do not support enum.
         _ => {
             let span = proc_macro2::Span::call_site();
-            let error = syn::Error::new(span, "#[derive(Parser)] only support named structs");
+            let error = syn::Error::new(span, "#[derive(Parser)] only support structs");
             Err(error)
         }
     }
 }
 
-fn collect_args_meta(fields: &FieldsNamed) -> syn::Result<Vec<MetaArg>> {
+/// Generics of the parsing methods, which depend on whether the struct borrows from the input.
+struct InputGenerics {
+    generics: TokenStream,
+    word: TokenStream,
+    provider: TokenStream,
+    bounds: TokenStream,
+}
+
+/// Build the generics of the parsing methods. If the struct borrows from the input, then the
+/// words are borrowed with the same lifetime, otherwise any kind of words can be parsed. The
+/// provided values are borrowed as the words, since they are parsed alike.
+fn build_input_generics(generics: &Generics, parser: &TokenStream) -> InputGenerics {
+    match generics.lifetimes().next() {
+        Some(param) => {
+            let lifetime = &param.lifetime;
+            InputGenerics {
+                generics: quote!(<I>),
+                word: quote!(&#lifetime str),
+                provider: quote!(&#lifetime P),
+                bounds: quote!(),
+            }
+        }
+        None => InputGenerics {
+            generics: quote!(<I, W>),
+            word: quote!(W),
+            provider: quote!(&P),
+            bounds: quote!(W: #parser::Word,),
+        },
+    }
+}
+
+/// Build the items of a struct which cannot be derived, with the same signatures as the derived
+/// ones, so that their main uses do not add more errors. Parsing always fails, and the other
+/// methods are left out.
+fn build_placeholder(input: &DeriveInput, paths: &Paths) -> TokenStream {
+    let Paths { parser, error, .. } = paths;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let InputGenerics {
+        generics,
+        word,
+        bounds,
+        ..
+    } = build_input_generics(&input.generics, parser);
+
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub const ABOUT: &'static str = "";
+
+            pub const ARGS: &'static [#parser::ArgInfo<'static>] = &[];
+
+            pub fn lookup_table() -> &'static #parser::ArgLookupTable<'static> {
+                static LOOKUP_TABLE: #parser::ArgLookupTable<'_> =
+                    #parser::ArgLookupTable::new(&[]);
+                &LOOKUP_TABLE
+            }
+
+            pub fn try_parse_from #generics (input: I) -> Result<Self, #error>
+            where
+                I: IntoIterator,
+                I::Item: #parser::IntoWord<#word>,
+                #bounds
+            {
+                let _ = input;
+                Err(#parser::Error::InvalidInput.into())
+            }
+        }
+    }
+}
+
+/// Build the parsers from the process arguments, which are only available with `std`. The
+/// command is named after the program, so that its help and usage match how it is called.
+fn build_std_parser(
+    ident: &Ident,
+    env: &TokenStream,
+    parser: &TokenStream,
+    error: &TokenStream,
+) -> TokenStream {
+    quote! {
+        impl #ident {
+            pub fn try_parse() -> Result<Self, #error> {
+                #env::try_parse_with(|argv| Self::try_parse_from(argv))
+            }
+
+            pub fn parse() -> Self {
+                let name = #env::program_name();
+                let cmd = #parser::CommandInfo::new(&name, Self::ARGS)
+                    .with_about(Self::ABOUT)
+                    .with_lookup_table(Self::lookup_table());
+
+                #env::parse_or_exit(&cmd, |argv| Self::try_parse_from(argv))
+            }
+        }
+    }
+}
+
+/// Collect the arguments of the fields. The fields with invalid attributes are left out, and
+/// their errors are added.
+fn collect_args_meta(fields: &Fields, errors: &mut Errors) -> Vec<MetaArg> {
     let meta = fields
-        .named
         .iter()
-        .map(|x| {
-            let attrs = Attr::parse_all(&x.attrs)?;
-            Result::<_, syn::Error>::Ok(MetaArg::new(x, attrs))
+        .enumerate()
+        .filter_map(|(i, x)| {
+            let attrs = errors.check(Attr::parse_all(&x.attrs))?;
+            errors.check(MetaArg::new(x, i, attrs))
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect::<Vec<_>>();
 
-    Ok(meta)
+    errors.check(check_positional_args(&meta));
+
+    meta
 }
 
-fn build_args_init(fields: &[MetaArg], ident: Ident) -> syn::Result<TokenStream> {
-    let args = fields
+/// Check that the positional arguments can be told apart: the required ones come first, then the
+/// optional ones, and the sequence of the remaining values, if any, is the last one.
+fn check_positional_args(args: &[MetaArg]) -> syn::Result<()> {
+    let mut errors = Errors::new();
+    let mut optional = false;
+    let mut sequence = false;
+
+    for arg in args
         .iter()
-        .map(|x| build_arg_parser(x, ident.clone()))
-        .collect::<Result<Vec<_>, syn::Error>>()?;
+        .filter(|x| x.is_positional() && !is_skipped_arg(x))
+    {
+        if sequence {
+            errors.push(syn::Error::new(
+                arg.span,
+                "expected no positional argument after a sequence of values",
+            ));
+        }
+
+        match arg.kind {
+            Ty::Simple if optional => {
+                errors.push(syn::Error::new(
+                    arg.span,
+                    "expected no required positional argument after an optional one",
+                ));
+            }
+            Ty::Simple => {}
+            Ty::Option => optional = true,
+            Ty::Vec | Ty::OptionVec => sequence = true,
+
+            // The type is reported while building the parser.
+            _ => {}
+        }
+    }
+
+    errors.finish()
+}
+
+fn build_args_init(
+    fields: &[MetaArg],
+    ident: Ident,
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let mut position = 0;
+
+    let args = Errors::collect(fields.iter().map(|x| {
+        if is_skipped_arg(x) {
+            build_skipped_arg(x)
+        } else if x.is_positional() && !is_trailing_arg(x) {
+            position += 1;
+            build_positional_arg_parser(x, position - 1, ident.clone(), parser)
+        } else {
+            build_arg_parser(x, ident.clone(), parser)
+        }
+    }))?;
 
     Ok(quote! {{
         #(
@@ -102,24 +482,310 @@ fn build_args_init(fields: &[MetaArg], ident: Ident) -> syn::Result<TokenStream>
     }})
 }
 
-fn build_arg_parser(arg: &MetaArg, args_ident: Ident) -> syn::Result<TokenStream> {
+/// Build the update of the fields given on the command line, which leaves the other ones
+/// untouched, and reports which fields are changed, i.e. given with another value than their
+/// current one. All the values are parsed before any field is assigned, so that the fields are
+/// left untouched on error.
+fn build_args_update(
+    fields: &[MetaArg],
+    args_ident: Ident,
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let mut position = 0usize;
+    let mut values = Vec::new();
+    let mut changes = Vec::new();
+
+    for (i, arg) in fields.iter().enumerate() {
+        // Raw arguments have no identifier, and only collect what is not defined.
+        if is_skipped_arg(arg) || is_raw_arg(arg) {
+            continue;
+        }
+
+        let var = format_ident!("update_{}", i);
+        let member = &arg.member;
+        let ty = &arg.ty;
+        let id = arg.id.unraw().to_string();
+
+        let (value, field) = if arg.is_positional() && !is_trailing_arg(arg) {
+            let inner_ty = get_inner_ty(ty, arg.kind);
+            position += 1;
+            let position = position - 1;
+
+            match arg.kind {
+                Ty::Simple => (
+                    quote_spanned!(ty.span()=> #args_ident.try_get_positional::<#inner_ty>(#position)?),
+                    quote!(value),
+                ),
+                Ty::Option => (
+                    quote_spanned!(ty.span()=> #args_ident.try_get_positional::<#inner_ty>(#position)?),
+                    quote!(Some(value)),
+                ),
+                Ty::Vec => (
+                    quote_spanned! { ty.span()=>
+                        #args_ident.try_get_positionals::<_, #inner_ty>(#position)
+                            .map(#parser::utils::non_empty_vec)?
+                    },
+                    quote!(value),
+                ),
+                Ty::OptionVec => (
+                    quote_spanned! { ty.span()=>
+                        #args_ident.try_get_positionals::<_, #inner_ty>(#position)
+                            .map(#parser::utils::non_empty_vec)?
+                    },
+                    quote!(Some(value)),
+                ),
+
+                // SAFETY: the positional arguments have already been checked.
+                _ => unreachable!(),
+            }
+        } else {
+            let value = build_arg_value(arg, args_ident.clone(), parser)?;
+
+            (
+                quote! {
+                    if #args_ident.contains(#id) {
+                        Some(#value)
+                    } else {
+                        None
+                    }
+                },
+                quote!(value),
+            )
+        };
+
+        values.push(quote_spanned! { arg.span=>
+            let #var = #value;
+        });
+
+        // A field set to its current value is not changed. The comparison is spanned to the field
+        // type, which must implement `PartialEq`.
+        let compare = quote_spanned!(ty.span()=> self.#member != value);
+        changes.push(quote! {
+            (#id, match #var {
+                Some(value) => {
+                    let value = #field;
+                    let changed = #compare;
+                    self.#member = value;
+                    changed
+                }
+                None => false,
+            })
+        });
+    }
+
+    // The parsed arguments are still checked, even if no field can be updated.
+    let unused = if changes.is_empty() {
+        quote!(let _ = #args_ident;)
+    } else {
+        TokenStream::new()
+    };
+
+    Ok(quote! {
+        #(#values)*
+        #unused
+
+        Ok(#parser::ChangedArgs::new([#(#changes),*]))
+    })
+}
+
+/// Build the description of the arguments, which are either given by flags or positional. The
+/// fields which are skipped or collect raw arguments are left out.
+fn build_args_info(
+    args: &[MetaArg],
+    rename: RenameRule,
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let mut items = Vec::new();
+
+    for arg in args {
+        if is_skipped_arg(arg) || is_raw_arg(arg) {
+            continue;
+        }
+
+        let id = arg.id.unraw().to_string();
+        let inner_ty = get_inner_ty(&arg.ty, arg.kind);
+
+        let flag = if arg.is_positional() {
+            None
+        } else {
+            build_canonical_flag(arg, rename, parser)?.map(|flag| quote!(.with_flag(#flag)))
+        };
+
+        let help = Some(&arg.doc)
+            .filter(|x| !x.is_empty())
+            .map(|x| quote!(.with_help(#x)));
+
+        let required = match arg.kind {
+            Ty::Simple | Ty::Vec | Ty::Array | Ty::Map => Some(quote!(.with_required(true))),
+            _ => None,
+        };
+
+        let secret = find_attr_with(&arg.attrs, |x| {
+            x.kind == AttrKind::Arg && x.name == Some(AttrName::Secret)
+        })
+        .map(|_| quote!(.with_secret(true)));
+
+        let hidden = find_attr_with(&arg.attrs, |x| {
+            x.kind == AttrKind::Arg && x.name == Some(AttrName::Hide)
+        })
+        .map(|_| quote!(.with_hidden(true)));
+
+        let deprecated = parse_deprecated_note(arg)?.map(|note| quote!(.with_deprecated(#note)));
+
+        let possible_values = find_attr_with(&arg.attrs, |x| {
+            x.kind == AttrKind::Arg && x.name == Some(AttrName::ValueEnum)
+        })
+        .map(|_| {
+            quote_spanned! { inner_ty.span()=>
+                .with_possible_values(<#inner_ty as #parser::ValueEnum>::NAMES)
+            }
+        });
+
+        // A single value is checked by converting it to the field type. Borrowed values, and the
+        // values of inferred types or key-value pairs cannot be checked alone.
+        let validator = match (&inner_ty, arg.kind) {
+            (Type::Reference(_) | Type::Infer(_), _) | (_, Ty::Map | Ty::OptionMap) => None,
+            _ => Some(quote! {
+                .with_validator(|value| value.parse::<#inner_ty>().is_ok())
+            }),
+        };
+
+        items.push(quote! {
+            #parser::ArgInfo::new(#id)
+                #flag #help #required #secret #hidden #deprecated #possible_values #validator
+        });
+    }
+
+    Ok(quote! { [ #(#items),* ] })
+}
+
+/// Build the initialization of a field which is not an argument.
+fn build_skipped_arg(arg: &MetaArg) -> syn::Result<TokenStream> {
+    let member = &arg.member;
+
+    // SAFETY: the argument is known to be skipped.
+    let attr = find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Skip)
+    })
+    .unwrap();
+
+    let value = match &attr.value {
+        Some(AttrValue::Expr(expr)) => quote!(#expr),
+        Some(AttrValue::LitStr(lit)) => quote!(#lit),
+        None => quote_spanned!(arg.ty.span()=> Default::default()),
+    };
+
+    Ok(quote_spanned! { arg.span=>
+        #member: #value
+    })
+}
+
+/// Build the parser of a positional argument, given its position among the positional ones.
+fn build_positional_arg_parser(
+    arg: &MetaArg,
+    position: usize,
+    args_ident: Ident,
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let ty = &arg.ty;
+    let inner_ty = get_inner_ty(ty, arg.kind);
+    let member = &arg.member;
+
+    let value = match arg.kind {
+        // Required positional argument.
+        Ty::Simple => quote_spanned! { ty.span()=>
+            #args_ident.try_get_positional::<#inner_ty>(#position)
+                .and_then(#parser::utils::check_arg_is_missing)
+                .map(Option::unwrap)?
+        },
+
+        // Optional positional argument.
+        Ty::Option => quote_spanned! { ty.span()=>
+            #args_ident.try_get_positional::<#inner_ty>(#position)?
+        },
+
+        // Sequence of the remaining positional arguments, which may be empty.
+        Ty::Vec => quote_spanned! { ty.span()=>
+            #args_ident.try_get_positionals::<_, #inner_ty>(#position)?
+        },
+
+        // Non-empty sequence of the remaining positional arguments, if any.
+        Ty::OptionVec => quote_spanned! { ty.span()=>
+            #args_ident.try_get_positionals::<_, #inner_ty>(#position)
+                .map(#parser::utils::non_empty_vec)?
+        },
+
+        _ => {
+            return Err(syn::Error::new(
+                ty.span(),
+                "expected positional argument to be of type `T`, `Option<T>`, `Vec<T, _>` or `Option<Vec<T, _>>`",
+            ));
+        }
+    };
+
+    Ok(quote_spanned! { arg.span=>
+        #member: #value
+    })
+}
+
+fn build_arg_parser(
+    arg: &MetaArg,
+    args_ident: Ident,
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let member = &arg.member;
+    let value = build_arg_value(arg, args_ident, parser)?;
+
+    Ok(quote_spanned! { arg.span=>
+        #member: #value
+    })
+}
+
+/// Build the parser of the value of an argument given by flags.
+fn build_arg_value(
+    arg: &MetaArg,
+    args_ident: Ident,
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
     let ty = &arg.ty;
-    let inner_ty = get_inner_ty(ty);
+    let inner_ty = get_inner_ty(ty, arg.kind);
 
     let try_get_one = quote_spanned!(inner_ty.span()=> try_get_one::<#inner_ty>);
-    let try_get_many = quote_spanned!(inner_ty.span()=> try_get_many::<_, #inner_ty>);
 
-    let arg_ident = arg.id.unraw();
-    let arg_id = arg_ident.to_string();
+    // Values borrowed from the input are taken as is, instead of being parsed.
+    let try_get_many = if let Type::Reference(_) = inner_ty {
+        quote_spanned!(inner_ty.span()=> try_get_words::<_>)
+    } else {
+        quote_spanned!(inner_ty.span()=> try_get_many::<_, #inner_ty>)
+    };
+
+    let arg_id = arg.id.unraw().to_string();
+
+    if is_raw_arg(arg) {
+        let Ty::Vec = arg.kind else {
+            return Err(syn::Error::new(
+                ty.span(),
+                "expected raw arguments to be of type `Vec<_, _>`",
+            ));
+        };
+
+        let try_get_undefined = quote_spanned!(inner_ty.span()=> try_get_undefined::<_, #inner_ty>);
+
+        return Ok(quote_spanned! { arg.span=>
+            #args_ident.#try_get_undefined()?
+        });
+    }
+
+    let separator = get_attr_arg_separator_value(&arg.attrs)?;
 
-    let value = match Ty::from_syn_ty(ty) {
+    let value = match arg.kind {
         // Optional argument with required value.
         Ty::Option => quote_spanned! { ty.span()=>
             if #args_ident.contains(#arg_id) {
                 Some(
                     #args_ident.#try_get_one(#arg_id)
                         .map(Option::unwrap)
-                        .and_then(noshell::parser::utils::check_value_is_missing)
+                        .and_then(#parser::utils::check_value_is_missing)
                         .map(Option::unwrap)?
                 )
             } else {
@@ -144,7 +810,7 @@ fn build_arg_parser(arg: &MetaArg, args_ident: Ident) -> syn::Result<TokenStream
                 Some(
                     #args_ident.#try_get_many(#arg_id)
                         .map(Option::unwrap)
-                        .and_then(noshell::parser::utils::check_vec_is_missing)?
+                        .and_then(#parser::utils::check_vec_is_missing)?
                 )
             } else {
                 None
@@ -154,26 +820,248 @@ fn build_arg_parser(arg: &MetaArg, args_ident: Ident) -> syn::Result<TokenStream
         // Required argument with required non-empty sequence of values.
         Ty::Vec => quote_spanned! { ty.span()=>
             #args_ident.#try_get_many(#arg_id)
-                .and_then(noshell::parser::utils::check_arg_is_missing)
+                .and_then(#parser::utils::check_arg_is_missing)
                 .map(Option::unwrap)
-                .and_then(noshell::parser::utils::check_vec_is_missing)?
+                .and_then(#parser::utils::check_vec_is_missing)?
+        },
+
+        // Optional argument with a fixed number of values.
+        Ty::OptionArray => quote_spanned! { ty.span()=>
+            #args_ident.try_get_array::<#inner_ty, _>(#arg_id)?
+        },
+
+        // Required argument with a fixed number of values.
+        Ty::Array => quote_spanned! { ty.span()=>
+            #args_ident.try_get_array::<#inner_ty, _>(#arg_id)
+                .and_then(#parser::utils::check_arg_is_missing)
+                .map(Option::unwrap)?
+        },
+
+        // Optional argument with key-value pairs.
+        Ty::OptionMap => quote_spanned! { ty.span()=>
+            #args_ident.try_get_map::<#inner_ty>(#arg_id, #separator)?
+        },
+
+        // Required argument with key-value pairs.
+        Ty::Map => quote_spanned! { ty.span()=>
+            #args_ident.try_get_map::<#inner_ty>(#arg_id, #separator)
+                .and_then(#parser::utils::check_arg_is_missing)
+                .map(Option::unwrap)?
         },
 
         // Required argument with required value.
         Ty::Simple => quote_spanned! { ty.span()=>
             #args_ident.#try_get_one(#arg_id)
-                .and_then(noshell::parser::utils::check_arg_is_missing)
+                .and_then(#parser::utils::check_arg_is_missing)
                 .map(Option::unwrap)
-                .and_then(noshell::parser::utils::check_value_is_missing)
+                .and_then(#parser::utils::check_value_is_missing)
                 .map(Option::unwrap)?
         },
     };
 
-    Ok(quote_spanned! { arg.span=>
-        #arg_ident: #value
+    Ok(value)
+}
+
+/// Build the writer of the arguments back to a command line, so that parsing its output gives
+/// the same arguments. It is only built with `#[noshell(to_args)]`, since the values must then
+/// implement `Display`.
+fn build_to_args(
+    args: &[MetaArg],
+    rename: RenameRule,
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
+    // Raw arguments come first, so that undefined flags do not take other values, then flags
+    // without value are closed by the following flags, and positional arguments and trailing
+    // words come last.
+    let mut raws = Vec::new();
+    let mut bares = Vec::new();
+    let mut values = Vec::new();
+    let mut positionals = Vec::new();
+    let mut trailings = Vec::new();
+
+    for arg in args {
+        let field = &arg.member;
+
+        if is_skipped_arg(arg) {
+            continue;
+        }
+
+        if arg.is_positional() && !is_trailing_arg(arg) {
+            positionals.push(match arg.kind {
+                Ty::Option => quote! {
+                    if let Some(value) = &self.#field {
+                        writer.positional(value)?;
+                    }
+                },
+                Ty::Vec => quote! {
+                    for value in self.#field.iter() {
+                        writer.positional(value)?;
+                    }
+                },
+                Ty::OptionVec => quote! {
+                    if let Some(values) = &self.#field {
+                        for value in values.iter() {
+                            writer.positional(value)?;
+                        }
+                    }
+                },
+                _ => quote!(writer.positional(&self.#field)?;),
+            });
+
+            continue;
+        }
+
+        if is_raw_arg(arg) {
+            raws.push(quote!(writer.raw(self.#field.iter())?;));
+            continue;
+        }
+
+        let flag = build_canonical_flag(arg, rename, parser)?;
+
+        if is_trailing_arg(arg) {
+            let flag = match flag {
+                Some(flag) => quote!(Some(#flag)),
+                None => quote!(None),
+            };
+
+            trailings.push(match arg.kind {
+                Ty::OptionVec => quote! {
+                    if let Some(values) = &self.#field {
+                        writer.trailing(#flag, values.iter())?;
+                    }
+                },
+                _ => quote!(writer.trailing(#flag, self.#field.iter())?;),
+            });
+
+            continue;
+        }
+
+        // SAFETY: only trailing var args may have no flag.
+        let flag = flag.unwrap();
+        let separator = get_attr_arg_separator_value(&arg.attrs)?;
+
+        // The writer needs the number of values taken by the flag when parsed, so that the values
+        // of a short flag do not take the following words. Delimited values are single words.
+        let expected = if has_value_delimiter(arg) {
+            quote!(#parser::AtMost::One)
+        } else {
+            parse_atmost_with_kind(arg.kind, parser)
+        };
+
+        match arg.kind {
+            Ty::Simple => values.push(quote! {
+                writer.flag_with_value(#flag, &self.#field)?;
+            }),
+
+            Ty::Option => values.push(quote! {
+                if let Some(value) = &self.#field {
+                    writer.flag_with_value(#flag, value)?;
+                }
+            }),
+
+            Ty::OptionOption => {
+                bares.push(quote! {
+                    if let Some(None) = &self.#field {
+                        writer.flag(#flag)?;
+                    }
+                });
+                values.push(quote! {
+                    if let Some(Some(value)) = &self.#field {
+                        writer.flag_with_value(#flag, value)?;
+                    }
+                });
+            }
+
+            Ty::Vec | Ty::Array => values.push(quote! {
+                writer.flag_with_values(#flag, #expected, self.#field.iter())?;
+            }),
+
+            Ty::OptionVec | Ty::OptionArray => values.push(quote! {
+                if let Some(values) = &self.#field {
+                    writer.flag_with_values(#flag, #expected, values.iter())?;
+                }
+            }),
+
+            Ty::Map => values.push(quote! {
+                writer.flag_with_values(
+                    #flag,
+                    #expected,
+                    self.#field.iter().map(|(key, value)| #parser::writer::Pair(key, #separator, value)),
+                )?;
+            }),
+
+            Ty::OptionMap => {
+                bares.push(quote! {
+                    if matches!(&self.#field, Some(map) if map.is_empty()) {
+                        writer.flag(#flag)?;
+                    }
+                });
+                values.push(quote! {
+                    if let Some(map) = &self.#field {
+                        writer.flag_with_values(
+                            #flag,
+                            #expected,
+                            map.iter().map(|(key, value)| #parser::writer::Pair(key, #separator, value)),
+                        )?;
+                    }
+                });
+            }
+        }
+    }
+
+    let writable = raws.len() + bares.len() + values.len() + positionals.len() + trailings.len();
+
+    let writer = if writable == 0 {
+        quote!(let _ = out;)
+    } else {
+        quote!(let mut writer = #parser::ArgsWriter::new(out);)
+    };
+
+    Ok(quote! {
+        pub fn to_args(&self, out: &mut impl core::fmt::Write) -> core::fmt::Result {
+            #writer
+            #(#raws)*
+            #(#bares)*
+            #(#values)*
+            #(#positionals)*
+            #(#trailings)*
+            Ok(())
+        }
     })
 }
 
+/// Build the flag written for an argument, which is its first long flag, or its first short
+/// flag, or its default long flag. Trailing var args without flag have none.
+fn build_canonical_flag(
+    arg: &MetaArg,
+    rename: RenameRule,
+    parser: &TokenStream,
+) -> syn::Result<Option<TokenStream>> {
+    let id = arg.id.unraw().to_string();
+    let name = rename.apply(&id);
+
+    if let Some(attr) = find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Long)
+    }) {
+        let key = parse_attr_arg_long_arg(attr)?.unwrap_or(name);
+        return Ok(Some(quote!(#parser::lexer::Flag::Long(#key))));
+    }
+
+    if let Some(attr) = find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Short)
+    }) {
+        // SAFETY: one can ensure that a field identifier has at least one character.
+        let key = parse_attr_arg_short_arg(attr)?.unwrap_or_else(|| id.chars().next().unwrap());
+        return Ok(Some(quote!(#parser::lexer::Flag::Short(#key))));
+    }
+
+    if is_trailing_arg(arg) {
+        return Ok(None);
+    }
+
+    Ok(Some(quote!(#parser::lexer::Flag::Long(#name))))
+}
+
 fn find_attr_with<P>(attrs: &[Attr], mut predicate: P) -> Option<&Attr>
 where
     P: FnMut(&Attr) -> bool,
@@ -237,6 +1125,407 @@ fn get_noshell_attr_limit_arg_value(attrs: &[Attr]) -> syn::Result<Option<usize>
     Ok(None)
 }
 
+fn parse_attr_arg_char_value(attr: &Attr) -> syn::Result<char> {
+    parse_attr_of_literal_expr_with(attr, |lit| {
+        if let Lit::Char(val) = lit {
+            Ok(val.value())
+        } else {
+            Err(syn::Error::new(
+                attr.id.span(),
+                format!("expected `{}` to be a character", attr.id),
+            ))
+        }
+    })?
+    .ok_or_else(|| {
+        syn::Error::new(
+            attr.id.span(),
+            format!("missing value of {} in `arg` attribute", attr.id),
+        )
+    })
+}
+
+fn get_attr_arg_separator_value(attrs: &[Attr]) -> syn::Result<char> {
+    match find_attr_with(attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Separator)
+    }) {
+        Some(attr) => parse_attr_arg_char_value(attr),
+        None => Ok(DEFAULT_KEY_VALUE_SEPARATOR),
+    }
+}
+
+pub fn build_rename_rule(attrs: &[Attr]) -> syn::Result<RenameRule> {
+    let Some(attr) = find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::RenameAll)
+    }) else {
+        return Ok(RenameRule::None);
+    };
+
+    parse_attr_of_literal_string_with(attr, |lit| {
+        RenameRule::from_name(&lit.value()).ok_or_else(|| {
+            syn::Error::new(
+                lit.span(),
+                "expected `rename_all` to be one of \"kebab-case\", \"snake_case\", \"camelCase\", \"lowercase\" or \"UPPERCASE\"",
+            )
+        })
+    })?
+    .ok_or_else(|| {
+        syn::Error::new(
+            attr.id.span(),
+            "missing value of rename_all in `noshell` attribute",
+        )
+    })
+}
+
+fn build_flag_matching(attrs: &[Attr], parser: &TokenStream) -> TokenStream {
+    let abbrev = find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::Abbrev)
+    });
+
+    match abbrev {
+        Some(_) => quote!(.with_matching(#parser::FlagMatching::Prefix)),
+        None => TokenStream::new(),
+    }
+}
+
+fn build_parsing_mode(attrs: &[Attr], parser: &TokenStream) -> TokenStream {
+    let options_first = find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::OptionsFirst)
+    });
+
+    match options_first {
+        Some(_) => quote!(.with_mode(#parser::ParsingMode::OptionsFirst)),
+        None => TokenStream::new(),
+    }
+}
+
+fn has_to_args_attr(attrs: &[Attr]) -> bool {
+    find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::ToArgs)
+    })
+    .is_some()
+}
+
+fn has_update_attr(attrs: &[Attr]) -> bool {
+    find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::Update)
+    })
+    .is_some()
+}
+
+fn is_trailing_arg(arg: &MetaArg) -> bool {
+    find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::TrailingVarArg)
+    })
+    .is_some()
+}
+
+fn has_flag_attr(arg: &MetaArg) -> bool {
+    find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && matches!(x.name, Some(AttrName::Short | AttrName::Long))
+    })
+    .is_some()
+}
+
+fn build_trailing(args: &[MetaArg]) -> syn::Result<TokenStream> {
+    let mut trailings = args
+        .iter()
+        .filter(|x| !is_skipped_arg(x) && is_trailing_arg(x));
+    let trailing = trailings.next();
+
+    if let Some(other) = trailings.next() {
+        return Err(syn::Error::new(
+            other.id.span(),
+            "must have at most one trailing var arg field",
+        ));
+    }
+
+    // A trailing var arg with a flag is handled in the lookup table, otherwise it starts at the
+    // first positional word after the positional arguments, if any.
+    match trailing {
+        Some(arg) if arg.is_positional() => {
+            let (Ty::Vec | Ty::OptionVec) = arg.kind else {
+                return Err(syn::Error::new(
+                    arg.ty.span(),
+                    "expected trailing var args to be of type `Vec<_, _>` or `Option<Vec<_, _>>`",
+                ));
+            };
+
+            let id = arg.id.unraw().to_string();
+            let after = args
+                .iter()
+                .take_while(|x| x.member != arg.member)
+                .filter(|x| x.is_positional() && !is_skipped_arg(x))
+                .count();
+
+            Ok(quote!(.with_trailing(#id).with_trailing_after(#after)))
+        }
+        Some(arg) if !has_flag_attr(arg) => {
+            let id = arg.id.unraw().to_string();
+            Ok(quote!(.with_trailing(#id)))
+        }
+        _ => Ok(TokenStream::new()),
+    }
+}
+
+fn build_arg_options(args: &[MetaArg], parser: &TokenStream) -> syn::Result<TokenStream> {
+    let mut errors = Errors::new();
+
+    // The options are looked up by flag, so that positional arguments cannot be deprecated.
+    for arg in args.iter().filter(|x| x.is_positional()) {
+        if let Some(attr) = find_deprecated_attr(arg) {
+            errors.push(syn::Error::new(
+                attr.id.span(),
+                "positional arguments cannot be deprecated",
+            ));
+        }
+    }
+
+    // The options are sorted by identifier, as the parser expects.
+    let mut flags = args.iter().filter(|x| is_flag_arg(x)).collect::<Vec<_>>();
+    flags.sort_by_cached_key(|x| x.id.unraw().to_string());
+
+    let items = errors.check_or_default(Errors::collect(
+        flags.into_iter().map(|x| build_arg_option(x, parser)),
+    ));
+    errors.finish()?;
+
+    let items = items.into_iter().flatten().collect::<Vec<_>>();
+
+    if items.is_empty() {
+        return Ok(TokenStream::new());
+    }
+
+    Ok(quote!(.with_options(&[#(#items),*])))
+}
+
+/// Build the options of an argument, if any differs from the default ones.
+fn build_arg_option(arg: &MetaArg, parser: &TokenStream) -> syn::Result<Option<TokenStream>> {
+    let require_equals = find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::RequireEquals)
+    })
+    .map(|_| quote!(.with_require_equals(true)));
+
+    let default_missing = match find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::DefaultMissingValue)
+    }) {
+        Some(attr) => {
+            let value = parse_attr_of_literal_string_with(attr, |lit| Ok(lit.value()))?
+                .ok_or_else(|| {
+                    syn::Error::new(
+                        attr.id.span(),
+                        "expected `default_missing_value` to be a literal string",
+                    )
+                })?;
+
+            Some(quote!(.with_default_missing(#value)))
+        }
+        None => None,
+    };
+
+    let delimiter = match find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::ValueDelimiter)
+    }) {
+        Some(attr) => {
+            let (Ty::Vec | Ty::OptionVec | Ty::Array | Ty::OptionArray) = arg.kind else {
+                return Err(syn::Error::new(
+                    attr.id.span(),
+                    "expected delimited values to be of type `Vec<_, _>`, `[_; _]` or their `Option`",
+                ));
+            };
+
+            let value = parse_attr_arg_char_value(attr)?;
+            Some(quote!(.with_delimiter(#value)))
+        }
+        None => None,
+    };
+
+    // The separator is only needed when the values are not read by the derived code (e.g. when
+    // deserialized), since it is otherwise given to `try_get_map`.
+    let separator = match find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Separator)
+    }) {
+        Some(attr) => {
+            let value = parse_attr_arg_char_value(attr)?;
+            Some(quote!(.with_separator(#value)))
+        }
+        None => None,
+    };
+
+    let setting = match find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Setting)
+    }) {
+        Some(attr) => {
+            if is_raw_arg(arg) {
+                return Err(syn::Error::new(
+                    attr.id.span(),
+                    "raw arguments cannot be given by a setting",
+                ));
+            }
+
+            let key = parse_attr_of_literal_string_with(attr, |lit| Ok(lit.value()))?.ok_or_else(
+                || syn::Error::new(attr.id.span(), "expected `setting` to be a literal string"),
+            )?;
+
+            Some(quote!(.with_setting(#key)))
+        }
+        None => None,
+    };
+
+    let deprecated = parse_deprecated_note(arg)?.map(|note| quote!(.with_deprecated(#note)));
+
+    // The values are checked while parsing, so that a misspelled one is reported along with the
+    // closest possible one.
+    let possible_values = find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::ValueEnum)
+    })
+    .map(|_| {
+        let inner_ty = get_inner_ty(&arg.ty, arg.kind);
+        quote_spanned! { inner_ty.span()=>
+            .with_possible_values(<#inner_ty as #parser::ValueEnum>::NAMES)
+        }
+    });
+
+    // Only the arguments with non-default options are listed.
+    if require_equals.is_none()
+        && default_missing.is_none()
+        && delimiter.is_none()
+        && separator.is_none()
+        && setting.is_none()
+        && deprecated.is_none()
+        && possible_values.is_none()
+    {
+        return Ok(None);
+    }
+
+    let id = arg.id.unraw().to_string();
+    Ok(Some(quote! {
+        (#id, #parser::ArgOptions::new()
+            #require_equals #default_missing #delimiter #separator #setting #deprecated
+            #possible_values)
+    }))
+}
+
+fn has_value_delimiter(arg: &MetaArg) -> bool {
+    find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::ValueDelimiter)
+    })
+    .is_some()
+}
+
+fn find_deprecated_attr(arg: &MetaArg) -> Option<&Attr> {
+    find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Deprecated)
+    })
+}
+
+/// Parse the note of a deprecated argument, which is empty if not given.
+fn parse_deprecated_note(arg: &MetaArg) -> syn::Result<Option<String>> {
+    let Some(attr) = find_deprecated_attr(arg) else {
+        return Ok(None);
+    };
+
+    if attr.value.is_none() {
+        return Ok(Some(String::new()));
+    }
+
+    parse_attr_of_literal_string_with(attr, |lit| Ok(lit.value()))?
+        .ok_or_else(|| {
+            syn::Error::new(
+                attr.id.span(),
+                "expected `deprecated` to be a literal string",
+            )
+        })
+        .map(Some)
+}
+
+fn build_deprecation_hook(attrs: &[Attr]) -> syn::Result<TokenStream> {
+    let attr = find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::DeprecationHook)
+    });
+
+    match attr {
+        Some(attr) => {
+            let path = parse_attr_path_value(attr)?;
+            Ok(quote!(.with_deprecation_hook(#path)))
+        }
+        None => Ok(TokenStream::new()),
+    }
+}
+
+fn is_skipped_arg(arg: &MetaArg) -> bool {
+    find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Skip)
+    })
+    .is_some()
+}
+
+/// Check if the argument is given by flags (i.e. a named field which is not skipped).
+fn is_flag_arg(arg: &MetaArg) -> bool {
+    !arg.is_positional() && !is_skipped_arg(arg)
+}
+
+fn is_raw_arg(arg: &MetaArg) -> bool {
+    find_attr_with(&arg.attrs, |x| {
+        x.kind == AttrKind::Arg && x.name == Some(AttrName::Raw)
+    })
+    .is_some()
+}
+
+fn build_undefined_policy(
+    attrs: &[Attr],
+    args: &[MetaArg],
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let mut raws = args.iter().filter(|x| is_flag_arg(x) && is_raw_arg(x));
+    let raw = raws.next();
+
+    if let Some(other) = raws.next() {
+        return Err(syn::Error::new(
+            other.id.span(),
+            "must have at most one raw arguments field",
+        ));
+    }
+
+    let attr = find_attr_with(attrs, |x| {
+        x.kind == AttrKind::NoShell && x.name == Some(AttrName::Undefined)
+    });
+
+    let policy = match attr {
+        Some(attr) => parse_attr_of_literal_string_with(attr, |lit| match lit.value().as_str() {
+            "error" => Ok(format_ident!("Error")),
+            "ignore" => Ok(format_ident!("Ignore")),
+            "collect" => Ok(format_ident!("Collect")),
+            _ => Err(syn::Error::new(
+                lit.span(),
+                "expected `undefined` to be one of \"error\", \"ignore\" or \"collect\"",
+            )),
+        })?
+        .ok_or_else(|| {
+            syn::Error::new(
+                attr.id.span(),
+                "missing value of undefined in `noshell` attribute",
+            )
+        })?,
+
+        // Raw arguments can only be filled if undefined flags are collected.
+        None if raw.is_some() => format_ident!("Collect"),
+
+        // Keep the default policy.
+        None => return Ok(TokenStream::new()),
+    };
+
+    if let Some(raw) = raw
+        && policy != "Collect"
+    {
+        return Err(syn::Error::new(
+            raw.id.span(),
+            "raw arguments require undefined flags to be collected",
+        ));
+    }
+
+    Ok(quote!(.with_undefined(#parser::UndefinedPolicy::#policy)))
+}
+
 fn parse_attr_arg_short_arg(attr: &Attr) -> syn::Result<Option<char>> {
     parse_attr_of_literal_expr_with(attr, |lit| {
         if let Lit::Char(val) = lit {
@@ -254,124 +1543,243 @@ fn parse_attr_arg_long_arg(attr: &Attr) -> syn::Result<Option<String>> {
     parse_attr_of_literal_string_with(attr, |lit| Ok(lit.value()))
 }
 
-fn build_arg_lookup_table(args: &[MetaArg]) -> syn::Result<TokenStream> {
+fn build_arg_lookup_table(
+    args: &[MetaArg],
+    rename: RenameRule,
+    parser: &TokenStream,
+) -> syn::Result<TokenStream> {
     let mut items = Vec::new();
+    let mut errors = Errors::new();
 
     let mut short_keys: HashSet<char> = HashSet::new();
     let mut long_keys: HashSet<String> = HashSet::new();
 
-    for arg in args {
-        // The argument identifier.
-        let id = arg.id.unraw().to_string();
+    for arg in args.iter().filter(|x| is_flag_arg(x) && !is_raw_arg(x)) {
+        let result = build_arg_lookup_items(
+            arg,
+            rename,
+            parser,
+            &mut short_keys,
+            &mut long_keys,
+            &mut errors,
+        );
 
-        // Check for short flags.
-        let shorts = arg
-            .attrs
-            .iter()
-            .filter(|x| x.kind == AttrKind::Arg && x.name == Some(AttrName::Short))
-            .map(|attr| Result::<_, syn::Error>::Ok((attr, parse_attr_arg_short_arg(attr)?)))
-            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(x) = errors.check(result) {
+            items.extend(x);
+        }
+    }
 
-        let mut errors = None;
+    errors.finish()?;
 
-        for (i, (attr, key)) in shorts.iter().enumerate() {
-            // SAFETY: one can ensure that a field identifier has at least one character.
-            let key = key.unwrap_or_else(|| id.chars().next().unwrap());
+    // The entries are sorted as the parser expects (see `ArgLookupTable::with_sorted`), so that
+    // flags are looked up by binary search.
+    items.sort_by(|a, b| a.0.cmp(&b.0));
 
-            if !short_keys.insert(key) {
-                return Err(syn::Error::new(
-                    attr.id.span(),
-                    format!("short flag `{}` is already used elsewhere", key),
-                ));
-            }
+    let items = items.into_iter().map(|(flag, id, atmost)| {
+        let flag = flag.to_tokens(parser);
+        quote! { (#flag, #id, #atmost) }
+    });
 
-            let flag = quote!(noshell::parser::lexer::Flag::Short(#key));
-            let atmost = parse_atmost_with_type(&arg.ty);
-            items.push(quote! { (#flag, #id, #atmost) });
+    Ok(quote! { [ #(#items),* ] })
+}
 
-            if i > 0 {
-                if errors.is_none() {
-                    errors = Some(syn::Error::new(
-                        attr.id.span(),
-                        "must have at most one short flag",
-                    ));
-                }
+/// Flag of an entry of the lookup table, ordered as the parser expects: short flags first, by
+/// character, then long flags, by name.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum FlagKey {
+    Short(char),
+    Long(String),
+}
 
-                errors.as_mut().unwrap().combine(syn::Error::new(
-                    attr.id.span(),
-                    "another short flag is defined here",
-                ));
-            }
+impl FlagKey {
+    fn to_tokens(&self, parser: &TokenStream) -> TokenStream {
+        match self {
+            FlagKey::Short(key) => quote!(#parser::lexer::Flag::Short(#key)),
+            FlagKey::Long(key) => quote!(#parser::lexer::Flag::Long(#key)),
         }
+    }
+}
+
+/// Entry of the lookup table, made of a flag, the argument identifier and its number of values.
+type LookupItem = (FlagKey, String, TokenStream);
+
+/// Build the entries of the lookup table of an argument. The flags which are already used are
+/// reported, but do not prevent to check the other flags.
+fn build_arg_lookup_items(
+    arg: &MetaArg,
+    rename: RenameRule,
+    parser: &TokenStream,
+    short_keys: &mut HashSet<char>,
+    long_keys: &mut HashSet<String>,
+    errors: &mut Errors,
+) -> syn::Result<Vec<LookupItem>> {
+    let mut items = Vec::new();
+
+    // The argument identifier.
+    let id = arg.id.unraw().to_string();
 
-        if let Some(err) = errors {
-            return Err(err);
+    // The default long flag, which is the renamed identifier.
+    let name = rename.apply(&id);
+
+    let atmost = parse_atmost_with_arg(arg, parser)?;
+
+    // Check for short flags.
+    let shorts = Errors::collect(
+        arg.attrs
+            .iter()
+            .filter(|x| x.kind == AttrKind::Arg && x.name == Some(AttrName::Short))
+            .map(|attr| Ok((attr, parse_attr_arg_short_arg(attr)?))),
+    )?;
+
+    let mut many = None;
+
+    for (i, (attr, key)) in shorts.iter().enumerate() {
+        // SAFETY: one can ensure that a field identifier has at least one character.
+        let key = key.unwrap_or_else(|| id.chars().next().unwrap());
+
+        if !short_keys.insert(key) {
+            errors.push(syn::Error::new(
+                attr.id.span(),
+                format!("short flag `{}` is already used elsewhere", key),
+            ));
         }
 
-        // Check for long flags.
-        let longs = arg
-            .attrs
+        if i > 0 {
+            let err = many.get_or_insert_with(|| {
+                syn::Error::new(attr.id.span(), "must have at most one short flag")
+            });
+
+            err.combine(syn::Error::new(
+                attr.id.span(),
+                "another short flag is defined here",
+            ));
+        }
+
+        items.push((FlagKey::Short(key), id.clone(), atmost.clone()));
+    }
+
+    if let Some(err) = many {
+        errors.push(err);
+    }
+
+    // Check for long flags.
+    let longs = Errors::collect(
+        arg.attrs
             .iter()
             .filter(|x| x.kind == AttrKind::Arg && x.name == Some(AttrName::Long))
-            .map(|attr| Result::<_, syn::Error>::Ok((attr, parse_attr_arg_long_arg(attr)?)))
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|attr| Ok((attr, parse_attr_arg_long_arg(attr)?))),
+    )?;
 
-        let mut errors = None;
+    let mut many = None;
 
-        for (i, (attr, key)) in longs.iter().enumerate() {
-            // SAFETY: one can ensure that a field identifier has at least one character.
-            let key = key.as_ref().unwrap_or(&id);
+    for (i, (attr, key)) in longs.iter().enumerate() {
+        let key = key.as_ref().unwrap_or(&name);
+
+        if !long_keys.insert(key.clone()) {
+            errors.push(syn::Error::new(
+                attr.id.span(),
+                format!("long flag `{}` is already used elsewhere", key),
+            ));
+        }
+
+        if i > 0 {
+            let err = many.get_or_insert_with(|| {
+                syn::Error::new(attr.id.span(), "must have at most one long flag")
+            });
+
+            err.combine(syn::Error::new(
+                attr.id.span(),
+                "another long flag is defined here",
+            ));
+        }
+
+        items.push((FlagKey::Long(key.clone()), id.clone(), atmost.clone()));
+    }
+
+    if let Some(err) = many {
+        errors.push(err);
+    }
+
+    // If the argument has no defined short or long flag, add a long flag by default. This
+    // default long flag has the same value as the field. Trailing var args without flag are
+    // positional instead.
+    // TODO: make this case as positional argument.
+    if shorts.is_empty() && longs.is_empty() && !is_trailing_arg(arg) {
+        if !long_keys.insert(name.clone()) {
+            errors.push(syn::Error::new(
+                arg.id.span(),
+                format!("long flag `{}` is already used elsewhere", name),
+            ));
+        }
+
+        items.push((FlagKey::Long(name.clone()), id.clone(), atmost.clone()));
+    }
+
+    // Aliases are additional flags, which are never written back.
+    for attr in arg.attrs.iter().filter(|x| {
+        x.kind == AttrKind::Arg && matches!(x.name, Some(AttrName::Alias | AttrName::ShortAlias))
+    }) {
+        let flag = if attr.name == Some(AttrName::Alias) {
+            let Some(key) = errors.check(parse_attr_arg_long_arg(attr).and_then(|x| {
+                x.ok_or_else(|| {
+                    syn::Error::new(attr.id.span(), "expected `alias` to be a literal string")
+                })
+            })) else {
+                continue;
+            };
 
             if !long_keys.insert(key.clone()) {
-                return Err(syn::Error::new(
+                errors.push(syn::Error::new(
                     attr.id.span(),
                     format!("long flag `{}` is already used elsewhere", key),
                 ));
             }
 
-            let flag = quote!(noshell::parser::lexer::Flag::Long(#key));
-            let atmost = parse_atmost_with_type(&arg.ty);
-            items.push(quote! { (#flag, #id, #atmost) });
-
-            if i > 0 {
-                if errors.is_none() {
-                    errors = Some(syn::Error::new(
-                        attr.id.span(),
-                        "must have at most one long flag",
-                    ));
-                }
+            FlagKey::Long(key)
+        } else {
+            let Some(key) = errors.check(parse_attr_arg_char_value(attr)) else {
+                continue;
+            };
 
-                errors.as_mut().unwrap().combine(syn::Error::new(
+            if !short_keys.insert(key) {
+                errors.push(syn::Error::new(
                     attr.id.span(),
-                    "another long flag is defined here",
+                    format!("short flag `{}` is already used elsewhere", key),
                 ));
             }
-        }
 
-        // If the argument has no defined short or long flag, add a long flag by default. This
-        // default long flag has the same value as the field.
-        // TODO: make this case as positional argument.
-        if shorts.is_empty() && longs.is_empty() {
-            if !long_keys.insert(id.clone()) {
-                return Err(syn::Error::new(
-                    arg.id.span(),
-                    format!("long flag `{}` is already used elsewhere", id),
-                ));
-            }
+            FlagKey::Short(key)
+        };
 
-            let flag = quote!(noshell::parser::lexer::Flag::Long(#id));
-            let atmost = parse_atmost_with_type(&arg.ty);
-            items.push(quote! { (#flag, #id, #atmost) });
-        }
+        items.push((flag, id.clone(), atmost.clone()));
     }
 
-    Ok(quote! { [ #(#items),* ] })
+    Ok(items)
 }
 
-fn parse_atmost_with_type(ty: &Type) -> TokenStream {
-    match Ty::from_syn_ty(ty) {
-        Ty::Simple | Ty::Option | Ty::OptionOption => quote!(noshell::parser::AtMost::One),
-        Ty::Vec | Ty::OptionVec => quote!(noshell::parser::AtMost::Many),
+fn parse_atmost_with_arg(arg: &MetaArg, parser: &TokenStream) -> syn::Result<TokenStream> {
+    let ty = arg.kind;
+
+    if is_trailing_arg(arg) {
+        let (Ty::Vec | Ty::OptionVec) = ty else {
+            return Err(syn::Error::new(
+                arg.ty.span(),
+                "expected trailing var args to be of type `Vec<_, _>` or `Option<Vec<_, _>>`",
+            ));
+        };
+
+        return Ok(quote!(#parser::AtMost::Trailing));
+    }
+
+    Ok(parse_atmost_with_kind(arg.kind, parser))
+}
+
+fn parse_atmost_with_kind(kind: Ty, parser: &TokenStream) -> TokenStream {
+    match kind {
+        Ty::Simple | Ty::Option | Ty::OptionOption => quote!(#parser::AtMost::One),
+        Ty::Vec | Ty::OptionVec | Ty::Array | Ty::OptionArray | Ty::Map | Ty::OptionMap => {
+            quote!(#parser::AtMost::Many)
+        }
     }
 }
 
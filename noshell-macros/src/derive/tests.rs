@@ -13,8 +13,8 @@ fn it_should_build_id_lookup_table_with_no_attrs() -> anyhow::Result<()> {
     let attrs = Attr::parse_all(&field.attrs)?;
     assert_eq!(0, attrs.len());
 
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_lookup_table(&[meta])?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_lookup_table(&[meta], RenameRule::None, &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => format!("input: `{}`", field.into_token_stream().to_string()),
@@ -36,8 +36,8 @@ fn it_should_build_id_lookup_table_with_one_default_long_flag() -> anyhow::Resul
     let attrs = Attr::parse_all(&field.attrs)?;
     assert_eq!(1, attrs.len());
 
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_lookup_table(&[meta])?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_lookup_table(&[meta], RenameRule::None, &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => format!("input: `{}`", field.into_token_stream().to_string()),
@@ -59,8 +59,8 @@ fn it_should_build_id_lookup_table_with_one_long_flag() -> anyhow::Result<()> {
     let attrs = Attr::parse_all(&field.attrs)?;
     assert_eq!(1, attrs.len());
 
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_lookup_table(&[meta])?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_lookup_table(&[meta], RenameRule::None, &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => format!("input: `{}`", field.into_token_stream().to_string()),
@@ -82,8 +82,8 @@ fn it_should_build_id_lookup_table_with_one_default_short_flag() -> anyhow::Resu
     let attrs = Attr::parse_all(&field.attrs)?;
     assert_eq!(1, attrs.len());
 
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_lookup_table(&[meta])?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_lookup_table(&[meta], RenameRule::None, &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => format!("input: `{}`", field.into_token_stream().to_string()),
@@ -105,8 +105,8 @@ fn it_should_build_id_lookup_table_with_one_short_flag() -> anyhow::Result<()> {
     let attrs = Attr::parse_all(&field.attrs)?;
     assert_eq!(1, attrs.len());
 
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_lookup_table(&[meta])?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_lookup_table(&[meta], RenameRule::None, &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => format!("input: `{}`", field.into_token_stream().to_string()),
@@ -128,8 +128,8 @@ fn it_should_build_id_lookup_table_with_one_short_and_one_long_flags() -> anyhow
     let attrs = Attr::parse_all(&field.attrs)?;
     assert_eq!(2, attrs.len());
 
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_lookup_table(&[meta])?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_lookup_table(&[meta], RenameRule::None, &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => format!("input: `{}`", field.into_token_stream().to_string()),
@@ -146,8 +146,8 @@ fn it_should_build_parser_for_simple_type() -> anyhow::Result<()> {
     let field: syn::Field = syn::parse_quote!(value: u32);
 
     let attrs = Attr::parse_all(&field.attrs)?;
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_parser(&meta, format_ident!("__args"))?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_parser(&meta, format_ident!("__args"), &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => field.into_token_stream().to_string(),
@@ -164,8 +164,8 @@ fn it_should_build_parser_for_option_type() -> anyhow::Result<()> {
     let field: syn::Field = syn::parse_quote!(value: Option<u32>);
 
     let attrs = Attr::parse_all(&field.attrs)?;
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_parser(&meta, format_ident!("__args"))?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_parser(&meta, format_ident!("__args"), &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => field.into_token_stream().to_string(),
@@ -182,8 +182,8 @@ fn it_should_build_parser_for_option_option_type() -> anyhow::Result<()> {
     let field: syn::Field = syn::parse_quote!(value: Option<Option<u32>>);
 
     let attrs = Attr::parse_all(&field.attrs)?;
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_parser(&meta, format_ident!("__args"))?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_parser(&meta, format_ident!("__args"), &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => field.into_token_stream().to_string(),
@@ -200,8 +200,8 @@ fn it_should_build_parser_for_option_vec_type() -> anyhow::Result<()> {
     let field: syn::Field = syn::parse_quote!(value: Option<Vec<u32>>);
 
     let attrs = Attr::parse_all(&field.attrs)?;
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_parser(&meta, format_ident!("__args"))?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_parser(&meta, format_ident!("__args"), &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => field.into_token_stream().to_string(),
@@ -218,8 +218,8 @@ fn it_should_build_parser_for_vec_type() -> anyhow::Result<()> {
     let field: syn::Field = syn::parse_quote!(value: Vec<u32, 8>);
 
     let attrs = Attr::parse_all(&field.attrs)?;
-    let meta = MetaArg::new(&field, attrs);
-    let output = build_arg_parser(&meta, format_ident!("__args"))?;
+    let meta = MetaArg::new(&field, 0, attrs)?;
+    let output = build_arg_parser(&meta, format_ident!("__args"), &quote!(noshell::parser))?;
 
     insta::with_settings!({
         description => field.into_token_stream().to_string(),
@@ -234,6 +234,7 @@ fn it_should_build_parser_for_vec_type() -> anyhow::Result<()> {
 #[test]
 fn it_should_build_struct_derive() -> anyhow::Result<()> {
     let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(update)]
         struct MyArgs {
             value1: u32,
             value2: u32,
@@ -245,3 +246,499 @@ fn it_should_build_struct_derive() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn it_should_build_std_parser() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            value: u32,
+        }
+    };
+
+    let output = format_rust_token_stream(build_std_parser(
+        &derive.ident,
+        &quote!(noshell::env),
+        &quote!(noshell::parser),
+        &quote!(noshell::Error),
+    ));
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_build_struct_derive_with_raw_args() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(to_args, update)]
+        struct MyArgs {
+            value: u32,
+
+            #[arg(raw)]
+            rest: Vec<String<16>, 8>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_reject_raw_args_with_ignored_undefined_flags() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(undefined = "ignore")]
+        struct MyArgs {
+            #[arg(raw)]
+            rest: Vec<String<16>, 8>,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+}
+
+#[test]
+fn it_should_build_struct_derive_with_trailing_var_args() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(to_args)]
+        struct MyArgs<'a> {
+            count: u32,
+
+            #[arg(trailing_var_arg)]
+            command: Vec<&'a str, 8>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_build_tuple_struct_derive_with_trailing_var_args() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(to_args)]
+        struct Repeat<'a>(u32, #[arg(trailing_var_arg)] Vec<&'a str, 8>);
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct Repeat(u32, #[arg(trailing_var_arg)] u32);
+    };
+
+    let err = try_run(&derive).unwrap_err();
+    assert_eq!(
+        "expected trailing var args to be of type `Vec<_, _>` or `Option<Vec<_, _>>`",
+        err.to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_should_build_struct_derive_with_optional_value_only_bound_with_equals() -> anyhow::Result<()>
+{
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(to_args)]
+        struct MyArgs {
+            #[arg(long, require_equals, default_missing_value = "always")]
+            color: Option<Option<String<8>>>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_build_struct_derive_with_key_value_maps() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(to_args)]
+        struct MyArgs {
+            set: FnvIndexMap<String<8>, u32, 4>,
+
+            #[arg(separator = ':')]
+            env: Option<LinearMap<String<8>, String<8>, 4>>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_build_struct_derive_with_delimited_values() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(value_delimiter = ',')]
+            channels: Vec<u8, 8>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_reject_delimited_values_of_single_value_type() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(value_delimiter = ',')]
+            channel: u8,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+}
+
+#[test]
+fn it_should_build_struct_derive_with_settings() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(setting = "uart.baud")]
+            baud: u32,
+
+            verbose: Option<bool>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_reject_raw_args_given_by_setting() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(raw, setting = "raw")]
+            raw: Vec<&'a str, 8>,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+}
+
+#[test]
+fn it_should_build_struct_derive_with_crate_path() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(crate = "platform::shell")]
+        struct MyArgs {
+            #[arg(short)]
+            count: Option<u32>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_reject_both_crate_and_parser_crate_paths() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(crate = "noshell", parser_crate = "noshell_parser")]
+        struct MyArgs {
+            count: u32,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+}
+
+#[test]
+fn it_should_build_tuple_struct_derive_with_skipped_field() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(update)]
+        struct MyArgs(
+            u32,
+            #[arg(skip = 42)] u8,
+            Option<u32>,
+            Vec<u32, 8>,
+        );
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_build_unit_struct_derive() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyCommand;
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_reject_ambiguous_positional_args() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs(Option<u32>, u32);
+    };
+
+    assert!(try_run(&derive).is_err());
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs(Vec<u32, 8>, Option<u32>);
+    };
+
+    assert!(try_run(&derive).is_err());
+}
+
+#[test]
+fn it_should_build_struct_derive_with_renamed_flags_and_aliases() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(rename_all = "kebab-case")]
+        struct MyArgs {
+            #[arg(alias = "retries", alias = "max_retries", short_alias = 'r')]
+            max_retries: u32,
+
+            #[arg(long)]
+            dry_run: Option<Option<bool>>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_reject_alias_already_used_elsewhere() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(alias = "count")]
+            retries: u32,
+
+            count: u32,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(short)]
+            retries: u32,
+
+            #[arg(short_alias = 'r')]
+            count: u32,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(rename_all = "kebab")]
+        struct MyArgs {
+            count: u32,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+}
+
+#[test]
+fn it_should_report_all_errors_at_once() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(undefined = "panic")]
+        struct MyArgs {
+            #[arg(short = 1)]
+            retries: u32,
+
+            #[arg(value_delimiter = ',')]
+            count: u32,
+
+            #[arg(skip)]
+            #[arg(long = "other")]
+            other: u32,
+        }
+    };
+
+    let err = try_run(&derive).unwrap_err();
+    let messages = err.into_iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(3, messages.len(), "{messages:?}");
+    assert!(messages.contains(&"expected `short` to be a character".to_string()));
+}
+
+#[test]
+fn it_should_reject_unknown_attributes() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(shrot)]
+            retries: u32,
+        }
+    };
+
+    let err = try_run(&derive).unwrap_err();
+    assert_eq!("unknown attribute `shrot` in `arg`", err.to_string());
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(short)]
+        struct MyArgs {
+            retries: u32,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+}
+
+#[test]
+fn it_should_define_methods_of_underivable_struct() {
+    let item = quote! {
+        struct MyArgs {
+            #[arg(shrot)]
+            retries: u32,
+        }
+    };
+
+    let output = run(item).to_string();
+
+    assert!(output.contains("compile_error"));
+    assert!(output.contains("fn try_parse_from < I , W >"));
+    assert!(output.contains("IntoWord < W >"));
+    assert!(!output.contains("fn try_parse_with"));
+    assert!(!output.contains("unimplemented"));
+}
+
+#[test]
+fn it_should_build_possible_values_of_value_enum() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(value_enum)]
+            parity: Option<Parity>,
+        }
+    };
+
+    let output = try_run(&derive)?.to_string();
+    assert!(
+        output.contains(
+            "with_possible_values (< Parity as noshell :: parser :: ValueEnum > :: NAMES)"
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_should_only_build_to_args_on_demand() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            retries: u32,
+        }
+    };
+
+    assert!(!try_run(&derive)?.to_string().contains("fn to_args"));
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(to_args = false)]
+        struct MyArgs {
+            retries: u32,
+        }
+    };
+
+    assert!(!try_run(&derive)?.to_string().contains("fn to_args"));
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(to_args)]
+        struct MyArgs {
+            retries: u32,
+        }
+    };
+
+    assert!(try_run(&derive)?.to_string().contains("fn to_args"));
+
+    Ok(())
+}
+
+#[test]
+fn it_should_reject_switch_attributes_with_other_values() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(abbrev = "no")]
+        struct MyArgs {
+            #[arg(hide = 0)]
+            retries: u32,
+        }
+    };
+
+    let err = try_run(&derive).unwrap_err();
+    assert_eq!(2, err.into_iter().count());
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(abbrev = false)]
+        struct MyArgs {
+            #[arg(hide = false)]
+            retries: u32,
+        }
+    };
+
+    let output = try_run(&derive).unwrap().to_string();
+    assert!(!output.contains("Prefix"));
+    assert!(!output.contains("with_hidden"));
+}
+
+#[test]
+fn it_should_reject_unknown_kind_of_type() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs {
+            #[arg(kind = "list")]
+            channels: Channels,
+        }
+    };
+
+    assert!(try_run(&derive).is_err());
+
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs([u8; 4]);
+    };
+
+    assert!(try_run(&derive).is_err());
+}
+
+#[test]
+fn it_should_build_struct_derive_with_hidden_and_deprecated_args() -> anyhow::Result<()> {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        #[noshell(deprecation_hook = noshell::env::warn_deprecated)]
+        struct MyArgs {
+            #[arg(deprecated = "use --baud")]
+            baud_rate: Option<u32>,
+
+            #[arg(hide)]
+            factory_reset: Option<bool>,
+        }
+    };
+
+    let output = format_rust_token_stream(try_run(&derive)?);
+    insta::assert_snapshot!(output);
+
+    Ok(())
+}
+
+#[test]
+fn it_should_reject_deprecated_positional_args() {
+    let derive: syn::DeriveInput = syn::parse_quote! {
+        struct MyArgs(#[arg(deprecated)] u32);
+    };
+
+    let err = try_run(&derive).unwrap_err();
+    assert_eq!("positional arguments cannot be deprecated", err.to_string());
+}
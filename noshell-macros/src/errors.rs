@@ -0,0 +1,77 @@
+//! Error helpers.
+
+/// Accumulator of errors, so that all of them are reported at once, at their own span.
+#[derive(Default)]
+pub struct Errors {
+    error: Option<syn::Error>,
+}
+
+impl Errors {
+    /// Create an empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an error.
+    pub fn push(&mut self, error: syn::Error) {
+        match &mut self.error {
+            Some(x) => x.combine(error),
+            None => self.error = Some(error),
+        }
+    }
+
+    /// Take the value of a result, if any, or add its error.
+    pub fn check<T>(&mut self, result: syn::Result<T>) -> Option<T> {
+        result.map_err(|err| self.push(err)).ok()
+    }
+
+    /// Take the value of a result, or add its error and fall back to the default value.
+    pub fn check_or_default<T: Default>(&mut self, result: syn::Result<T>) -> T {
+        self.check(result).unwrap_or_default()
+    }
+
+    /// Finish the accumulation, which fails if any error has been added.
+    pub fn finish(self) -> syn::Result<()> {
+        self.error.map_or(Ok(()), Err)
+    }
+
+    /// Collect the values of all the results, or all their errors.
+    pub fn collect<T, I>(results: I) -> syn::Result<Vec<T>>
+    where
+        I: IntoIterator<Item = syn::Result<T>>,
+    {
+        let mut errors = Errors::new();
+        let values = results
+            .into_iter()
+            .filter_map(|x| errors.check(x))
+            .collect();
+
+        errors.finish().map(|_| values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::Span;
+
+    use super::*;
+
+    #[test]
+    fn it_should_collect_all_errors() {
+        let results = [
+            Ok(1),
+            Err(syn::Error::new(Span::call_site(), "first")),
+            Ok(2),
+            Err(syn::Error::new(Span::call_site(), "second")),
+        ];
+
+        let err = Errors::collect(results).unwrap_err();
+        let messages = err.into_iter().map(|x| x.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(vec!["first", "second"], messages);
+        assert_eq!(
+            Ok(vec![1, 2]),
+            Errors::collect([Ok(1), Ok(2)]).map_err(|x| x.to_string())
+        );
+    }
+}
@@ -7,13 +7,22 @@ use proc_macro::TokenStream;
 mod arg;
 mod attr;
 mod derive;
+mod errors;
+mod rename;
 mod ty;
+mod value_enum;
 
 #[cfg(test)]
 mod tests;
 
 /// `Parser` derive macro.
-#[proc_macro_derive(Parser, attributes(arg))]
+#[proc_macro_derive(Parser, attributes(arg, noshell))]
 pub fn derive_parser(item: TokenStream) -> TokenStream {
     derive::run(item.into()).into()
 }
+
+/// `ValueEnum` derive macro, for enums whose values are given by name.
+#[proc_macro_derive(ValueEnum, attributes(noshell))]
+pub fn derive_value_enum(item: TokenStream) -> TokenStream {
+    value_enum::run(item.into()).into()
+}
@@ -0,0 +1,113 @@
+//! Renaming rules of flags.
+
+/// Rule applied to field identifiers to build their default long flag.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RenameRule {
+    /// Keep the identifier as is (e.g. `max_retries`).
+    #[default]
+    None,
+
+    /// Words separated by dashes (e.g. `max-retries`).
+    Kebab,
+
+    /// Words separated by underscores (e.g. `max_retries`).
+    Snake,
+
+    /// Words joined, with capitalized words but the first one (e.g. `maxRetries`).
+    Camel,
+
+    /// Words joined in lower case (e.g. `maxretries`).
+    Lower,
+
+    /// Words joined in upper case (e.g. `MAXRETRIES`).
+    Upper,
+}
+
+impl RenameRule {
+    /// Parse a rule from its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "kebab-case" => Some(RenameRule::Kebab),
+            "snake_case" => Some(RenameRule::Snake),
+            "camelCase" => Some(RenameRule::Camel),
+            "lowercase" => Some(RenameRule::Lower),
+            "UPPERCASE" => Some(RenameRule::Upper),
+            _ => None,
+        }
+    }
+
+    /// Apply the rule to a field identifier, whose words are separated by underscores.
+    pub fn apply(self, id: &str) -> String {
+        let words = id.split('_').filter(|x| !x.is_empty());
+
+        match self {
+            RenameRule::None => id.to_string(),
+            RenameRule::Kebab => words.map(str::to_lowercase).collect::<Vec<_>>().join("-"),
+            RenameRule::Snake => words.map(str::to_lowercase).collect::<Vec<_>>().join("_"),
+            RenameRule::Lower => words.map(str::to_lowercase).collect(),
+            RenameRule::Upper => words.map(str::to_uppercase).collect(),
+            RenameRule::Camel => words
+                .enumerate()
+                .map(|(i, x)| {
+                    let x = x.to_lowercase();
+                    let mut chars = x.chars();
+
+                    match chars.next() {
+                        Some(first) if i > 0 => first.to_uppercase().chain(chars).collect(),
+                        _ => x,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Split an identifier in camel case into words separated by underscores (e.g. `EvenParity` into
+/// `even_parity`), so that the rules can be applied to the variants of an enum.
+pub fn snake_case(id: &str) -> String {
+    let mut out = String::new();
+
+    for (i, c) in id.char_indices() {
+        if c.is_uppercase() && i > 0 && !id[..i].ends_with('_') {
+            out.push('_');
+        }
+
+        out.extend(c.to_lowercase());
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_apply_rename_rules() {
+        let id = "max_retries";
+
+        assert_eq!("max_retries", RenameRule::None.apply(id));
+        assert_eq!("max-retries", RenameRule::Kebab.apply(id));
+        assert_eq!("max_retries", RenameRule::Snake.apply(id));
+        assert_eq!("maxRetries", RenameRule::Camel.apply(id));
+        assert_eq!("maxretries", RenameRule::Lower.apply(id));
+        assert_eq!("MAXRETRIES", RenameRule::Upper.apply(id));
+    }
+
+    #[test]
+    fn it_should_split_camel_case_identifiers() {
+        assert_eq!("even_parity", snake_case("EvenParity"));
+        assert_eq!("none", snake_case("None"));
+        assert_eq!(
+            "even-parity",
+            RenameRule::Kebab.apply(&snake_case("EvenParity"))
+        );
+    }
+
+    #[test]
+    fn it_should_parse_rename_rules() {
+        assert_eq!(Some(RenameRule::Kebab), RenameRule::from_name("kebab-case"));
+        assert_eq!(Some(RenameRule::Camel), RenameRule::from_name("camelCase"));
+        assert_eq!(None, RenameRule::from_name("kebab"));
+    }
+}
@@ -2,26 +2,51 @@
 
 #![allow(dead_code)]
 
-use syn::{GenericArgument, Path, PathArguments, PathSegment, Type, TypePath};
+use syn::{GenericArgument, Path, PathArguments, PathSegment, Type, TypeArray, TypePath};
 
 /// Type variants that are supported by the parsers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub(crate) enum Ty {
+    Array,
+    Map,
     Option,
+    OptionArray,
+    OptionMap,
     OptionOption,
     OptionVec,
     Vec,
     Simple,
 }
 
+/// Names of the map types, which are filled with key-value pairs.
+const MAP_TY_NAMES: &[&str] = &["FnvIndexMap", "IndexMap", "LinearMap"];
+
+/// Modules of the option type, when given by a qualified path.
+const OPTION_TY_MODULES: &[&str] = &["core::option", "std::option"];
+
+/// Modules of the vector type, when given by a qualified path.
+const VEC_TY_MODULES: &[&str] = &["heapless", "heapless::vec"];
+
+/// Modules of the map types, when given by a qualified path.
+const MAP_TY_MODULES: &[&str] = &["heapless", "heapless::index_map", "heapless::linear_map"];
+
 impl Ty {
     pub(crate) fn from_syn_ty(ty: &Type) -> Self {
-        if has_ty_param_if_name(ty, "Vec") {
+        if is_array_ty(ty) {
+            Ty::Array
+        } else if has_ty_param_if_name(ty, "Vec") {
             Ty::Vec
+        } else if is_map_ty(ty) {
+            Ty::Map
         } else if let Some(param_ty) = get_ty_param_if_name(ty, "Option") {
             if has_ty_param_if_name(param_ty, "Option") {
                 Ty::OptionOption
             } else if has_ty_param_if_name(param_ty, "Vec") {
                 Ty::OptionVec
+            } else if is_map_ty(param_ty) {
+                Ty::OptionMap
+            } else if is_array_ty(param_ty) {
+                Ty::OptionArray
             } else {
                 Ty::Option
             }
@@ -29,18 +54,38 @@ impl Ty {
             Ty::Simple
         }
     }
+
+    /// Get the variant from its name in `#[arg(kind = "...")]`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "array" => Some(Ty::Array),
+            "map" => Some(Ty::Map),
+            "option" => Some(Ty::Option),
+            "option_array" => Some(Ty::OptionArray),
+            "option_map" => Some(Ty::OptionMap),
+            "option_option" => Some(Ty::OptionOption),
+            "option_vec" => Some(Ty::OptionVec),
+            "vec" => Some(Ty::Vec),
+            "simple" => Some(Ty::Simple),
+            _ => None,
+        }
+    }
 }
 
 // These following helpers have been take from the `clap` crate.
 
-pub(crate) fn get_inner_ty(ty: &Type) -> &Type {
-    let field_ty = Ty::from_syn_ty(ty);
+/// Get the type of the parsed values, given the variant of the field type. It is left to be
+/// inferred if the field type does not tell it (e.g. an alias).
+pub(crate) fn get_inner_ty(ty: &Type, kind: Ty) -> Type {
+    let inner_ty = match kind {
+        Ty::Option | Ty::OptionMap | Ty::Vec => get_ty_param(ty),
+        Ty::OptionOption | Ty::OptionVec => get_ty_param(ty).and_then(get_ty_param),
+        Ty::Array => get_array_elem_ty(ty),
+        Ty::OptionArray => get_ty_param(ty).and_then(get_array_elem_ty),
+        Ty::Map | Ty::Simple => Some(ty),
+    };
 
-    match field_ty {
-        Ty::Option | Ty::Vec => get_ty_param(ty).unwrap_or(ty),
-        Ty::OptionOption | Ty::OptionVec => get_ty_param(ty).and_then(get_ty_param).unwrap_or(ty),
-        Ty::Simple => ty,
-    }
+    inner_ty.cloned().unwrap_or_else(|| syn::parse_quote!(_))
 }
 
 pub(crate) fn is_simple_ty(ty: &Type, name: &str) -> bool {
@@ -55,25 +100,53 @@ pub(crate) fn is_simple_ty(ty: &Type, name: &str) -> bool {
         .unwrap_or(false)
 }
 
+pub(crate) fn is_map_ty(ty: &Type) -> bool {
+    MAP_TY_NAMES
+        .iter()
+        .any(|name| has_ty_param_if_name(ty, name))
+}
+
+pub(crate) fn is_array_ty(ty: &Type) -> bool {
+    get_array_elem_ty(ty).is_some()
+}
+
+pub(crate) fn get_array_elem_ty(ty: &Type) -> Option<&Type> {
+    match ungroup_ty(ty) {
+        Type::Array(TypeArray { elem, .. }) => Some(elem),
+        _ => None,
+    }
+}
+
 pub(crate) fn has_ty_param_if_name(ty: &Type, name: &str) -> bool {
     get_ty_param_if_name(ty, name).is_some()
 }
 
 pub(crate) fn get_ty_param(ty: &Type) -> Option<&Type> {
-    get_ty_param_if(ty, |_| true)
+    get_ty_param_if(ty, |_, _| true)
 }
 
+/// Get the first type parameter of the type with the given name, which is either used as is or
+/// qualified by one of its known modules (e.g. `core::option::Option`).
 pub(crate) fn get_ty_param_if_name<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
-    get_ty_param_if(ty, |x| x.ident == name)
+    let modules = match name {
+        "Option" => OPTION_TY_MODULES,
+        "Vec" => VEC_TY_MODULES,
+        _ if MAP_TY_NAMES.contains(&name) => MAP_TY_MODULES,
+        _ => &[],
+    };
+
+    get_ty_param_if(ty, |module, x| {
+        x.ident == name && (module.is_empty() || modules.contains(&module))
+    })
 }
 
 pub(crate) fn get_ty_param_if<F>(ty: &Type, f: F) -> Option<&Type>
 where
-    F: FnOnce(&PathSegment) -> bool,
+    F: FnOnce(&str, &PathSegment) -> bool,
 {
-    only_last_path_segment(ty)
-        .filter(|segment| f(segment))
-        .and_then(|segment| {
+    last_path_segment(ty)
+        .filter(|(module, segment)| f(module, segment))
+        .and_then(|(_, segment)| {
             if let PathArguments::AngleBracketed(args) = &segment.arguments {
                 // NOTE: Only consider the first type parameter, which is assumed to hold
                 // inner type.
@@ -90,26 +163,52 @@ where
         })
 }
 
-pub(crate) fn only_last_path_segment(mut ty: &Type) -> Option<&PathSegment> {
-    // What is this for?
-    while let Type::Group(syn::TypeGroup { elem, .. }) = ty {
-        ty = elem;
-    }
+pub(crate) fn only_last_path_segment(ty: &Type) -> Option<&PathSegment> {
+    last_path_segment(ty)
+        .filter(|(module, _)| module.is_empty())
+        .map(|(_, segment)| segment)
+}
 
-    match ty {
+/// Get the last segment of a type path, and the module which qualifies it, if any (e.g.
+/// `heapless::vec` for `heapless::vec::Vec<u8, 4>`).
+pub(crate) fn last_path_segment(ty: &Type) -> Option<(String, &PathSegment)> {
+    match ungroup_ty(ty) {
         Type::Path(TypePath {
             qself: None,
-            path:
-                Path {
-                    leading_colon: None,
-                    segments,
-                },
-        }) => only_one(segments.iter()),
+            path: Path { segments, .. },
+        }) => {
+            let last = segments.last()?;
+            let modules = segments.iter().take(segments.len() - 1);
+
+            // The modules cannot have generic arguments.
+            if modules
+                .clone()
+                .any(|x| !matches!(x.arguments, PathArguments::None))
+            {
+                return None;
+            }
+
+            let module = modules
+                .map(|x| x.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::");
+
+            Some((module, last))
+        }
 
         _ => None,
     }
 }
 
+/// Remove the invisible groups around a type, which may come from `macro_rules!` expansions.
+fn ungroup_ty(mut ty: &Type) -> &Type {
+    while let Type::Group(syn::TypeGroup { elem, .. }) = ty {
+        ty = elem;
+    }
+
+    ty
+}
+
 pub(crate) fn only_one<I, T>(mut iter: I) -> Option<T>
 where
     I: Iterator<Item = T>,
@@ -170,4 +269,67 @@ mod tests {
         let ty = syn::parse_quote!(core::ops::Option<i32>);
         assert!(!has_ty_param_if_name(&ty, "Option"));
     }
+
+    #[test]
+    fn it_should_match_map_type() {
+        let ty = syn::parse_quote!(FnvIndexMap<String<8>, u32, 4>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Map));
+
+        let ty = syn::parse_quote!(LinearMap<String<8>, u32, 4>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Map));
+
+        let ty = syn::parse_quote!(Option<IndexMap<String<8>, u32, S, 4>>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::OptionMap));
+
+        let ty = syn::parse_quote!(BTreeMap<String, u32>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Simple));
+    }
+
+    #[test]
+    fn it_should_match_qualified_types() {
+        let ty = syn::parse_quote!(core::option::Option<u32>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Option));
+
+        let ty = syn::parse_quote!(::std::option::Option<heapless::Vec<u8, 4>>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::OptionVec));
+
+        let ty = syn::parse_quote!(heapless::vec::Vec<u8, 4>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Vec));
+
+        let ty = syn::parse_quote!(heapless::FnvIndexMap<String<8>, u32, 4>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Map));
+
+        let ty = syn::parse_quote!(mod1::Vec<u8, 4>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Simple));
+
+        let ty = syn::parse_quote!(core::option::Option<u32>);
+        let inner: Type = syn::parse_quote!(u32);
+        assert_eq!(inner, get_inner_ty(&ty, Ty::Option));
+    }
+
+    #[test]
+    fn it_should_match_array_type() {
+        let inner: Type = syn::parse_quote!(u8);
+
+        let ty = syn::parse_quote!([u8; 4]);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Array));
+        assert_eq!(inner, get_inner_ty(&ty, Ty::Array));
+
+        let ty = syn::parse_quote!(Option<[u8; 4]>);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::OptionArray));
+        assert_eq!(inner, get_inner_ty(&ty, Ty::OptionArray));
+    }
+
+    #[test]
+    fn it_should_infer_inner_type_of_aliases() {
+        let ty = syn::parse_quote!(Channels);
+        assert!(matches!(Ty::from_syn_ty(&ty), Ty::Simple));
+
+        let inner: Type = syn::parse_quote!(_);
+        assert_eq!(inner, get_inner_ty(&ty, Ty::Vec));
+        assert_eq!(inner, get_inner_ty(&ty, Ty::OptionArray));
+
+        assert_eq!(Some(Ty::OptionVec), Ty::from_name("option_vec"));
+        assert_eq!(None, Ty::from_name("list"));
+    }
 }
@@ -0,0 +1,185 @@
+//! Derive of enumerations of values.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DataEnum, DeriveInput, Fields};
+
+use crate::attr::{Attr, AttrKind, AttrName};
+use crate::derive::{build_paths, build_rename_rule};
+use crate::errors::Errors;
+use crate::rename::{RenameRule, snake_case};
+
+pub fn run(item: TokenStream) -> TokenStream {
+    let input: DeriveInput = match syn::parse2(item) {
+        Ok(x) => x,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    try_run(&input).unwrap_or_else(|err| err.to_compile_error())
+}
+
+pub fn try_run(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+
+    let Data::Enum(DataEnum { ref variants, .. }) = input.data else {
+        let span = proc_macro2::Span::call_site();
+        return Err(syn::Error::new(
+            span,
+            "#[derive(ValueEnum)] only support enums",
+        ));
+    };
+
+    let mut errors = Errors::new();
+
+    if !input.generics.params.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            &input.generics,
+            "#[derive(ValueEnum)] does not support generics",
+        ));
+    }
+
+    let attrs = errors.check_or_default(Attr::parse_all(&input.attrs));
+
+    for attr in &attrs {
+        if !matches!(
+            attr.name,
+            Some(AttrName::Crate | AttrName::ParserCrate | AttrName::RenameAll)
+        ) {
+            errors.push(syn::Error::new(
+                attr.id.span(),
+                format!(
+                    "unsupported attribute `{}` in `noshell` of an enum",
+                    attr.id
+                ),
+            ));
+        }
+    }
+
+    let paths = errors.check(build_paths(&attrs));
+
+    // The names are in kebab case by default, as the flags usually are.
+    let renamed = attrs
+        .iter()
+        .any(|x| x.kind == AttrKind::NoShell && x.name == Some(AttrName::RenameAll));
+    let rename = if renamed {
+        errors.check_or_default(build_rename_rule(&attrs))
+    } else {
+        RenameRule::Kebab
+    };
+
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            errors.push(syn::Error::new_spanned(
+                &variant.fields,
+                "expected a variant without any field",
+            ));
+        }
+    }
+
+    errors.finish()?;
+
+    // SAFETY: the paths are only missing on error, which has already been returned.
+    let parser = paths.unwrap().parser;
+
+    let idents = variants.iter().map(|x| &x.ident).collect::<Vec<_>>();
+    let names = idents
+        .iter()
+        .map(|x| rename.apply(&snake_case(&x.to_string())))
+        .collect::<Vec<_>>();
+
+    Ok(quote! {
+        impl #parser::ValueEnum for #ident {
+            const NAMES: &'static [&'static str] = &[#(#names),*];
+
+            fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    #(#names => Some(Self::#idents),)*
+                    _ => None,
+                }
+            }
+
+            fn name(&self) -> &'static str {
+                match self {
+                    #(Self::#idents => #names,)*
+                }
+            }
+        }
+
+        impl core::str::FromStr for #ident {
+            type Err = #parser::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let names = <Self as #parser::ValueEnum>::NAMES;
+
+                <Self as #parser::ValueEnum>::from_name(s).ok_or_else(|| {
+                    #parser::Error::InvalidValue(#parser::utils::closest(s, names.iter().copied()))
+                })
+            }
+        }
+
+        impl core::fmt::Display for #ident {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(#parser::ValueEnum::name(self))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::utils::format_rust_token_stream;
+
+    use super::*;
+
+    #[test]
+    fn it_should_build_value_enum_derive() -> anyhow::Result<()> {
+        let derive: syn::DeriveInput = syn::parse_quote! {
+            enum Parity {
+                None,
+                Even,
+                OddParity,
+            }
+        };
+
+        let output = format_rust_token_stream(try_run(&derive)?);
+        insta::assert_snapshot!(output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_rename_values_of_enum() -> anyhow::Result<()> {
+        let derive: syn::DeriveInput = syn::parse_quote! {
+            #[noshell(parser_crate = "noshell_parser", rename_all = "UPPERCASE")]
+            enum Parity {
+                OddParity,
+            }
+        };
+
+        let output = try_run(&derive)?.to_string();
+        assert!(output.contains("\"ODDPARITY\""));
+        assert!(output.contains("noshell_parser :: ValueEnum"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_reject_variants_with_fields() {
+        let derive: syn::DeriveInput = syn::parse_quote! {
+            #[noshell(abbrev)]
+            enum Parity {
+                Even(u8),
+                Odd { bits: u8 },
+            }
+        };
+
+        let err = try_run(&derive).unwrap_err();
+        assert_eq!(3, err.into_iter().count());
+
+        let derive: syn::DeriveInput = syn::parse_quote! {
+            struct Parity;
+        };
+
+        assert!(try_run(&derive).is_err());
+    }
+}
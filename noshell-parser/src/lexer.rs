@@ -1,5 +1,6 @@
 //! A lexer for generating tokens from a command line.
 
+use core::fmt;
 use core::marker::PhantomData;
 use core::ops::Deref;
 
@@ -14,6 +15,15 @@ pub enum Flag<'a> {
     Long(&'a str),
 }
 
+impl fmt::Display for Flag<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Flag::Short(name) => write!(f, "-{}", name),
+            Flag::Long(name) => write!(f, "--{}", name),
+        }
+    }
+}
+
 /// Defines a `Token` that has been read from the command line.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -99,6 +109,38 @@ impl<'a> Token<'a> {
         }
     }
 
+    /// Convert a input string into a token, with the value attached to a long flag if any (e.g.
+    /// `--color=always`).
+    pub fn tokenize_with_value(input: &'a str) -> (Self, Option<&'a str>) {
+        match Self::tokenize(input) {
+            Token::Flag(Flag::Long(name)) => match name.split_once('=') {
+                Some((name, value)) => (Token::Flag(Flag::Long(name)), Some(value)),
+                None => (Token::Flag(Flag::Long(name)), None),
+            },
+            token => (token, None),
+        }
+    }
+
+    /// Convert an input made of bytes into a flag, if any, with the offset of the value attached
+    /// to a long flag (e.g. `--data=<bytes>`). Only valid UTF-8 flags are recognised, while the
+    /// attached value can be any bytes.
+    pub fn tokenize_bytes_with_value(input: &'a [u8]) -> Option<(Flag<'a>, Option<usize>)> {
+        let (text, valid) = match core::str::from_utf8(input) {
+            Ok(text) => (text, true),
+            Err(err) => {
+                // SAFETY: the input is valid UTF-8 up to this index.
+                let text = unsafe { core::str::from_utf8_unchecked(&input[..err.valid_up_to()]) };
+                (text, false)
+            }
+        };
+
+        match Self::tokenize_with_value(text) {
+            (Token::Flag(flag), Some(value)) => Some((flag, Some(text.len() - value.len()))),
+            (Token::Flag(flag), None) if valid => Some((flag, None)),
+            _ => None,
+        }
+    }
+
     /// Convert a input string into a token.
     pub fn tokenize(input: &'a str) -> Self {
         if let Some(flag) = Self::as_short_flag(input) {
@@ -185,6 +227,33 @@ mod tests {
             .is_equal_to(Token::Flag(Flag::Long("flag")));
     }
 
+    #[test]
+    fn it_should_match_long_flag_with_attached_value() {
+        assert_that!(Token::tokenize_with_value("--color=always"))
+            .is_equal_to((Token::Flag(Flag::Long("color")), Some("always")));
+
+        assert_that!(Token::tokenize_with_value("--color="))
+            .is_equal_to((Token::Flag(Flag::Long("color")), Some("")));
+
+        assert_that!(Token::tokenize_with_value("--color"))
+            .is_equal_to((Token::Flag(Flag::Long("color")), None));
+
+        assert_that!(Token::tokenize_with_value("a=b")).is_equal_to((Token::Value("a=b"), None));
+    }
+
+    #[test]
+    fn it_should_match_flags_of_bytes() {
+        assert_that!(Token::tokenize_bytes_with_value(b"--data=\xff\x00"))
+            .is_equal_to(Some((Flag::Long("data"), Some(7))));
+
+        assert_that!(Token::tokenize_bytes_with_value(b"-v"))
+            .is_equal_to(Some((Flag::Short('v'), None)));
+
+        assert_that!(Token::tokenize_bytes_with_value(b"--da\xffta")).is_none();
+        assert_that!(Token::tokenize_bytes_with_value(b"-\xff")).is_none();
+        assert_that!(Token::tokenize_bytes_with_value(b"\xfe--data")).is_none();
+    }
+
     #[test]
     fn it_should_match_numbers() {
         let lexer =
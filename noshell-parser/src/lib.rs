@@ -12,4 +12,15 @@ pub mod parser;
 /// Utilities.
 pub mod utils;
 
-pub use parser::{ArgLookupTable, AtMost, Error, ParsedArgs};
+/// Writer of arguments back to a command line.
+pub mod writer;
+
+pub use parser::{
+    ArgInfo, ArgLookupTable, ArgOptions, AtMost, Bytes, ChangedArgs, CommandInfo, Error,
+    FlagMatching, IntoWord, KeyValueMap, ParsedArgs, ParsingMode, Span, UndefinedPolicy, ValueEnum,
+    ValueProvider, ValueSource, ValueSources, Word,
+};
+pub use writer::ArgsWriter;
+
+#[cfg(feature = "serde")]
+pub use parser::de::from_args;
@@ -1,15 +1,33 @@
 //! A parser for collecting arguments from a token stream.
 
+use core::cmp::Ordering;
 use core::fmt::Debug;
+use core::ops::Range;
 use core::str::FromStr;
 
 use heapless::Vec;
 
 use crate::lexer::{Flag, Token};
+use crate::utils::{self, Candidates};
 
+#[cfg(feature = "serde")]
+pub mod de;
+
+mod changes;
+mod enums;
+mod info;
+mod maps;
+mod sources;
 mod values;
+mod words;
 
+pub use changes::ChangedArgs;
+pub use enums::ValueEnum;
+pub use info::{ArgInfo, CommandInfo};
+pub use maps::KeyValueMap;
+pub use sources::{ValueProvider, ValueSource, ValueSources};
 pub use values::{AtMost, Values};
+pub use words::{Bytes, IntoWord, Word};
 
 #[cfg(test)]
 mod tests;
@@ -23,11 +41,44 @@ pub enum Error {
     #[error("undefined argument")]
     UndefinedArgument,
 
+    /// The argument is not defined, but is close to a defined flag, which could be suggested to
+    /// the user.
+    #[error("undefined argument, did you mean `{0}`?")]
+    MisspelledArgument(Flag<'static>),
+
+    /// The argument is an ambiguous prefix of several defined flags, which are given as
+    /// candidates.
+    #[error("ambiguous argument, could be {0}")]
+    AmbiguousArgument(Candidates<Flag<'static>>),
+
     /// The argument value is invalid, meaning that it cannot be converted to the destination
     /// type. This could mean that there is a missing implementation for [`str::parse`] trait.
     #[error("invalid argument")]
     InvalidArgument,
 
+    /// The argument value is not one of its possible values. It holds the closest possible
+    /// value, if any, which could be suggested to the user.
+    #[error("invalid value{}", DidYouMean(.0))]
+    InvalidValue(Option<&'static str>),
+
+    /// The command is not one of the known subcommands. It holds the closest subcommand name, if
+    /// any, which could be suggested to the user.
+    #[error("unknown command{}", DidYouMean(.0))]
+    UnknownCommand(Option<&'static str>),
+
+    /// The command is an ambiguous prefix of several subcommand names, which are given as
+    /// candidates.
+    #[error("ambiguous command, could be {0}")]
+    AmbiguousCommand(Candidates<&'static str>),
+
+    /// The argument value is not a key-value pair, as the separator or the key is missing.
+    #[error("malformed key-value pair")]
+    MalformedPair,
+
+    /// The same key is given several times in key-value pairs.
+    #[error("duplicate key")]
+    DuplicateKey,
+
     /// The argument has no expected value on the command line.
     #[error("no value expected")]
     NoValueArgument,
@@ -37,133 +88,942 @@ pub enum Error {
     #[error("missing argument")]
     MissingArgument,
 
+    /// The input word cannot be read, which occurs when the command line lexer fails.
+    #[error("invalid input")]
+    InvalidInput,
+
     /// Insufficient space for parsing arguments.
     #[error("out of parser memory space")]
     OutOfMemory,
 }
 
+/// Helper for displaying a suggestion after an error message.
+struct DidYouMean<'a, T>(&'a Option<T>);
+
+impl<T: core::fmt::Display> core::fmt::Display for DidYouMean<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(flag) => write!(f, ", did you mean `{}`?", flag),
+            None => Ok(()),
+        }
+    }
+}
+
 /// Re-export of result type with module [`Error`].
 pub type Result<T, E = Error> = core::result::Result<T, E>;
 
 /// Defines an argument on the command line.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Arg<'a> {
-    /// A named argument, which is defined by a flag, and zero or more values.
-    Named(&'a str, Values<'a>),
+    /// A named argument, which is defined by a flag, and the location of its values.
+    Named(&'a str, Span),
 
-    /// A positional argument, which is defined by its value.
-    Positional(&'a str),
+    /// A named argument missing from the command line, whose value is given by a provider (see
+    /// [`ValueProvider`]).
+    Provided(&'a str, &'a str),
+
+    /// A positional argument, which is defined by the index of its value in the parsed words.
+    Positional(usize),
+
+    /// An undefined flag followed by its values, which is defined by the range of its words
+    /// (including the flag itself).
+    Undefined(Range<usize>),
+}
+
+impl<'a> Arg<'a> {
+    /// Compare two parsed arguments: the named arguments come first, ordered by identifier, then
+    /// by order of appearance, followed by the other arguments, by order of appearance.
+    fn cmp_parsed(&self, other: &Self) -> Ordering {
+        match (self.id(), other.id()) {
+            (Some(a), Some(b)) => a.cmp(b).then(self.position().cmp(&other.position())),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => self.position().cmp(&other.position()),
+        }
+    }
+
+    /// Get the identifier of a named argument.
+    fn id(&self) -> Option<&'a str> {
+        match self {
+            Arg::Named(id, _) | Arg::Provided(id, _) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Get the index of the first word of the argument. Provided values have no words.
+    fn position(&self) -> usize {
+        match self {
+            Arg::Named(_, span) => span.range.start,
+            Arg::Provided(..) => 0,
+            Arg::Positional(index) => *index,
+            Arg::Undefined(range) => range.start,
+        }
+    }
+}
+
+/// Defines the location of the values of a named argument.
+///
+/// The options of the argument (e.g. its delimiter) are looked up in the table when its values
+/// are read, so that they are not copied in every span.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Span {
+    /// The range of the values in the parsed words.
+    pub range: Range<usize>,
+
+    /// Whether the value is attached to its flag (e.g. `--color=always`), in which case the range
+    /// is the word of the flag itself.
+    pub attached: bool,
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span {
+            range,
+            ..Default::default()
+        }
+    }
+}
+
+/// Defines per-argument options, which are not part of the look-up table entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ArgOptions<'a> {
+    require_equals: bool,
+    default_missing: Option<&'a str>,
+    delimiter: Option<char>,
+    separator: Option<char>,
+    setting: Option<&'a str>,
+    deprecated: Option<&'a str>,
+    possible_values: &'a [&'a str],
+}
+
+impl<'a> ArgOptions<'a> {
+    /// Create the default options.
+    pub const fn new() -> Self {
+        ArgOptions {
+            require_equals: false,
+            default_missing: None,
+            delimiter: None,
+            separator: None,
+            setting: None,
+            deprecated: None,
+            possible_values: &[],
+        }
+    }
+
+    /// Set whether values can only be attached to the flag with an equal sign (e.g.
+    /// `--color=always`). If so, the word following the flag is never taken as its value.
+    pub const fn with_require_equals(mut self, require_equals: bool) -> Self {
+        self.require_equals = require_equals;
+        self
+    }
+
+    /// Set the value used when the flag is given without any value.
+    pub const fn with_default_missing(mut self, value: &'a str) -> Self {
+        self.default_missing = Some(value);
+        self
+    }
+
+    /// Set the delimiter of the values within a single word (e.g. `--channels 1,2,5`). If so,
+    /// the flag only takes the word following it, instead of all the words up to the next flag.
+    pub const fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Set the separator of the key-value pairs of the argument (e.g. `--env KEY:value`), when
+    /// they are not separated by an equal sign.
+    pub const fn with_separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// Set the key of the setting which gives the value when the argument is missing on the
+    /// command line (see [`ValueProvider`]).
+    pub const fn with_setting(mut self, key: &'a str) -> Self {
+        self.setting = Some(key);
+        self
+    }
+
+    /// Set the argument as deprecated, with a note (e.g. `use --foo`). It is still parsed, but
+    /// the deprecation hook of the look-up table is called when it is given on the command line
+    /// (see [`ArgLookupTable::with_deprecation_hook`]).
+    pub const fn with_deprecated(mut self, note: &'a str) -> Self {
+        self.deprecated = Some(note);
+        self
+    }
+
+    /// Set the possible values of the argument (e.g. the names of a [`ValueEnum`]). Any other
+    /// value is rejected with [`Error::InvalidValue`], along with the closest possible one.
+    pub const fn with_possible_values(mut self, values: &'a [&'a str]) -> Self {
+        self.possible_values = values;
+        self
+    }
+
+    /// Get whether values can only be attached with an equal sign.
+    pub const fn require_equals(&self) -> bool {
+        self.require_equals
+    }
+
+    /// Get the value used when the flag is given without any value.
+    pub const fn default_missing(&self) -> Option<&'a str> {
+        self.default_missing
+    }
+
+    /// Get the delimiter of the values within a single word.
+    pub const fn delimiter(&self) -> Option<char> {
+        self.delimiter
+    }
+
+    /// Get the separator of the key-value pairs, if not an equal sign.
+    pub const fn separator(&self) -> Option<char> {
+        self.separator
+    }
+
+    /// Get the key of the setting which gives the value of a missing argument.
+    pub const fn setting(&self) -> Option<&'a str> {
+        self.setting
+    }
+
+    /// Get the deprecation note, if the argument is deprecated.
+    pub const fn deprecated(&self) -> Option<&'a str> {
+        self.deprecated
+    }
+
+    /// Get the possible values, which is empty if any value is accepted.
+    pub const fn possible_values(&self) -> &'a [&'a str] {
+        self.possible_values
+    }
+}
+
+/// Defines how the parser handles flags that are not defined in the look-up table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UndefinedPolicy {
+    /// Fail with [`Error::UndefinedArgument`], or with [`Error::MisspelledArgument`] if the flag
+    /// is close to a defined one.
+    #[default]
+    Error,
+
+    /// Skip the undefined flag and its first value, if any. The next values are positional
+    /// arguments, since the flag is assumed to take at most one value.
+    Ignore,
+
+    /// Collect the undefined flag and all the values that follow it, up to the next flag or the
+    /// end of options (i.e. `--`), so that they can be forwarded as is (see
+    /// [`ParsedArgs::undefined`]). Positional arguments must then come before the undefined flag
+    /// or after `--`.
+    Collect,
+}
+
+/// Defines how long flags are matched against the look-up table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlagMatching {
+    /// Only the exact long flag matches.
+    #[default]
+    Exact,
+
+    /// Any unambiguous prefix of a long flag also matches (e.g. `--verb` for `--verbose`).
+    Prefix,
+}
+
+/// Defines how flags and positional arguments can be ordered on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParsingMode {
+    /// Flags and positional arguments can be freely intermixed.
+    #[default]
+    Intermixed,
+
+    /// Flags must come first, as in POSIX: the parsing of flags stops at the first positional
+    /// argument, and all the remaining words are positional arguments (e.g. the arguments of
+    /// another command).
+    OptionsFirst,
 }
 
 /// Argument id to metadata look-up table.
 #[derive(Debug)]
 pub struct ArgLookupTable<'a> {
     table: &'a [(Flag<'a>, &'a str, AtMost)],
+    undefined: UndefinedPolicy,
+    trailing: Option<&'a str>,
+    trailing_after: usize,
+    matching: FlagMatching,
+    mode: ParsingMode,
+    options: &'a [(&'a str, ArgOptions<'a>)],
+    deprecation_hook: Option<fn(&str, &str)>,
+    sorted: bool,
 }
 
 impl<'a> ArgLookupTable<'a> {
     /// Create a new look-up table.
     pub const fn new(table: &'a [(Flag<'a>, &'a str, AtMost)]) -> Self {
-        ArgLookupTable { table }
+        ArgLookupTable {
+            table,
+            undefined: UndefinedPolicy::Error,
+            trailing: None,
+            trailing_after: 0,
+            matching: FlagMatching::Exact,
+            mode: ParsingMode::Intermixed,
+            options: &[],
+            deprecation_hook: None,
+            sorted: false,
+        }
+    }
+
+    /// Set the policy on undefined flags.
+    pub const fn with_undefined(mut self, policy: UndefinedPolicy) -> Self {
+        self.undefined = policy;
+        self
+    }
+
+    /// Set the identifier of the argument that captures the first positional word and all the
+    /// remaining words, verbatim (see [`AtMost::Trailing`]).
+    pub const fn with_trailing(mut self, id: &'a str) -> Self {
+        self.trailing = Some(id);
+        self
+    }
+
+    /// Set the number of positional arguments which come before the trailing words, so that the
+    /// capture only starts after them (e.g. `1` for `repeat 5 <command...>`).
+    pub const fn with_trailing_after(mut self, count: usize) -> Self {
+        self.trailing_after = count;
+        self
+    }
+
+    /// Set how long flags are matched.
+    pub const fn with_matching(mut self, matching: FlagMatching) -> Self {
+        self.matching = matching;
+        self
+    }
+
+    /// Set how flags and positional arguments can be ordered.
+    pub const fn with_mode(mut self, mode: ParsingMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the options of some arguments, given by their identifier.
+    pub const fn with_options(mut self, options: &'a [(&'a str, ArgOptions<'a>)]) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the hook called with the identifier and the note of every deprecated argument given
+    /// on the command line (e.g. to print a warning).
+    pub const fn with_deprecation_hook(mut self, hook: fn(&str, &str)) -> Self {
+        self.deprecation_hook = Some(hook);
+        self
+    }
+
+    /// Declare that the entries are sorted by flag (i.e. short flags by character, then long flags
+    /// by name) and the options by identifier, so that they are looked up by binary search
+    /// instead of a linear scan.
+    ///
+    /// Panics if they are not sorted, which fails the build when the table is a `static`.
+    pub const fn with_sorted(mut self) -> Self {
+        let mut i = 1;
+
+        while i < self.table.len() {
+            if utils::cmp_flags(&self.table[i - 1].0, &self.table[i].0).is_ge() {
+                panic!("the look-up table entries are not sorted by flag");
+            }
+
+            i += 1;
+        }
+
+        let mut i = 1;
+
+        while i < self.options.len() {
+            if utils::cmp_str(self.options[i - 1].0, self.options[i].0).is_ge() {
+                panic!("the look-up table options are not sorted by identifier");
+            }
+
+            i += 1;
+        }
+
+        self.sorted = true;
+        self
+    }
+
+    /// Get the policy on undefined flags.
+    pub const fn undefined(&self) -> UndefinedPolicy {
+        self.undefined
     }
 
-    /// Look up for a flag.
+    /// Get the identifier of the argument that captures the trailing words, if any.
+    pub const fn trailing(&self) -> Option<&'a str> {
+        self.trailing
+    }
+
+    /// Get the number of positional arguments which come before the trailing words.
+    pub const fn trailing_after(&self) -> usize {
+        self.trailing_after
+    }
+
+    /// Get how long flags are matched.
+    pub const fn matching(&self) -> FlagMatching {
+        self.matching
+    }
+
+    /// Get how flags and positional arguments can be ordered.
+    pub const fn mode(&self) -> ParsingMode {
+        self.mode
+    }
+
+    /// Get the options of the argument with the given identifier.
+    pub fn options_of(&self, id: &str) -> ArgOptions<'a> {
+        let found = if self.sorted {
+            self.options
+                .binary_search_by(|x| x.0.cmp(id))
+                .ok()
+                .map(|i| &self.options[i])
+        } else {
+            self.options.iter().find(|x| x.0 == id)
+        };
+
+        found.map(|x| x.1).unwrap_or_default()
+    }
+
+    /// Iterate over all the flags of the argument with the given identifier, including its
+    /// aliases.
+    pub fn flags_of<'b>(&self, id: &'b str) -> impl Iterator<Item = Flag<'a>> + use<'a, 'b> {
+        self.table
+            .iter()
+            .filter(move |x| x.1 == id)
+            .map(|(flag, _, _)| *flag)
+    }
+
+    /// Look up for a flag. An ambiguous prefix is considered as not found.
     pub fn metadata_of(&self, flag: &Flag<'_>) -> Option<(&'a str, AtMost)> {
-        let (_, id, expected) = self.table.iter().find(|&x| x.0 == *flag)?;
-        Some((*id, *expected))
+        self.find(flag)
+            .ok()
+            .flatten()
+            .map(|(_, id, expected)| (*id, *expected))
+    }
+
+    /// Look up for the defined flag that is the closest to the given one, which is likely to be
+    /// a typo or an abbreviation. Only long flags are considered, since any short flag is close
+    /// to any other.
+    pub fn suggestion_for(&self, flag: &Flag<'_>) -> Option<Flag<'a>> {
+        let Flag::Long(name) = flag else {
+            return None;
+        };
+
+        let longs = || {
+            self.table.iter().filter_map(|x| match x.0 {
+                Flag::Long(name) => Some(name),
+                Flag::Short(_) => None,
+            })
+        };
+
+        utils::find_by_prefix(name, longs())
+            .ok()
+            .flatten()
+            .or_else(|| utils::closest(name, longs()))
+            .map(Flag::Long)
+    }
+
+    /// Find the entry of a flag, either by exact match or by unique prefix, depending on the
+    /// matching mode. On an ambiguous prefix, return the candidate entries.
+    #[allow(clippy::type_complexity)]
+    fn find(
+        &self,
+        flag: &Flag<'_>,
+    ) -> Result<Option<&'a (Flag<'a>, &'a str, AtMost)>, Candidates<Flag<'a>>> {
+        let exact = if self.sorted {
+            self.table
+                .binary_search_by(|x| utils::cmp_flags(&x.0, flag))
+                .ok()
+                .map(|i| &self.table[i])
+        } else {
+            self.table.iter().find(|&x| x.0 == *flag)
+        };
+
+        if let Some(entry) = exact {
+            return Ok(Some(entry));
+        }
+
+        let (FlagMatching::Prefix, Flag::Long(prefix)) = (self.matching, flag) else {
+            return Ok(None);
+        };
+
+        // The long flags starting with the prefix are contiguous in a sorted table.
+        let entries = if self.sorted {
+            let start = self
+                .table
+                .partition_point(|x| utils::cmp_flags(&x.0, flag).is_lt());
+            let len = self.table[start..]
+                .iter()
+                .take_while(|x| matches!(x.0, Flag::Long(name) if name.starts_with(prefix)))
+                .count();
+
+            &self.table[start..start + len]
+        } else {
+            self.table
+        };
+
+        let mut found: Option<&'a (Flag<'a>, &'a str, AtMost)> = None;
+        let mut candidates = Candidates::new();
+        let mut ambiguous = false;
+
+        for entry in entries {
+            let Flag::Long(name) = entry.0 else {
+                continue;
+            };
+
+            if !name.starts_with(prefix) {
+                continue;
+            }
+
+            candidates.push(entry.0);
+
+            // Several flags of the same argument are not ambiguous.
+            match found {
+                Some(other) if other.1 != entry.1 => ambiguous = true,
+                Some(_) => {}
+                None => found = Some(entry),
+            }
+        }
+
+        if ambiguous {
+            return Err(candidates);
+        }
+
+        Ok(found)
+    }
+}
+
+impl ArgLookupTable<'static> {
+    /// Try to look up for a flag, and fail with [`Error::AmbiguousArgument`] on an ambiguous
+    /// prefix.
+    pub fn try_metadata_of(&self, flag: &Flag<'_>) -> Result<Option<(&'static str, AtMost)>> {
+        self.find(flag)
+            .map(|x| x.map(|(_, id, expected)| (*id, *expected)))
+            .map_err(Error::AmbiguousArgument)
     }
 }
 
 /// Defines the result of argument parsing. This is a simple key-value store that offers a look-up
 /// over parsed arguments.
+///
+/// The parsed words are stored alongside the arguments, so that they can be either borrowed from
+/// the input (e.g. `&str`) or owned (e.g. `heapless::String`).
+///
+/// The words are buffered, rather than borrowed as a slice, so that any iterator is parsed in a
+/// single pass (e.g. the words split from a command line, which are never collected). The memory
+/// taken is then `CAPACITY * (size_of::<W>() + size_of::<Arg>())`, whatever the number of given
+/// words. Borrowed words are only copied as references, not as bytes, so that the cost of parsing
+/// a slice of `&str` is a reference per word.
+///
+/// Once parsed, the arguments are sorted in place by identifier, so that they are looked up by
+/// binary search without any extra memory.
 #[derive(Default, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct ParsedArgs<'a, const CAPACITY: usize = 1> {
+pub struct ParsedArgs<'a, const CAPACITY: usize = 1, W = &'a str> {
+    words: Vec<W, CAPACITY>,
     args: Vec<Arg<'a>, CAPACITY>,
+    table: Option<&'a ArgLookupTable<'a>>,
 }
 
-impl<'a, const CAPACITY: usize> ParsedArgs<'a, CAPACITY> {
+impl<'a, const CAPACITY: usize, W> ParsedArgs<'a, CAPACITY, W>
+where
+    W: Word,
+{
     /// Parse the command line input from a token stream. The result is the set of found arguments.
-    pub fn parse_from(argv: &'a [&'a str], ids: &ArgLookupTable<'static>) -> Self {
+    pub fn parse_from<I>(argv: I, ids: &'a ArgLookupTable<'static>) -> Self
+    where
+        I: IntoIterator,
+        I::Item: IntoWord<W>,
+    {
         Self::try_parse_from(argv, ids).expect("cannot parse arguments")
     }
 
     /// Try to parse the input arguments.
-    pub fn try_parse_from(
-        argv: &'a [&'a str],
-        table: &ArgLookupTable<'static>,
-    ) -> Result<Self, Error> {
-        // Some initial checks before start parsing.
-        Self::check_capacity(argv)?;
-        Self::check_undefined_argument(argv, table)?;
+    ///
+    /// The input is consumed in a single pass, so that words can be streamed from a lexer without
+    /// being collected first.
+    pub fn try_parse_from<I>(argv: I, table: &'a ArgLookupTable<'static>) -> Result<Self, Error>
+    where
+        I: IntoIterator,
+        I::Item: IntoWord<W>,
+    {
+        let mut parsed = ParsedArgs {
+            words: Vec::new(),
+            args: Vec::new(),
+            table: Some(table),
+        };
 
-        let mut parsed = ParsedArgs::default();
+        // The flag waiting for its values, if any.
+        let mut pending: Option<Pending<'a>> = None;
 
-        let lookup = |flag: &Flag<'a>| {
-            // SAFETY: the validation above guarantees that the lookup found an entry.
-            unsafe { table.metadata_of(flag).unwrap_unchecked() }
-        };
+        // Whether the parsing of options has stopped (see [`ParsingMode::OptionsFirst`]).
+        let mut options_done = false;
 
-        let named = |args: &mut Vec<_, _>, name, expected, (start, end)| {
-            let (rest, arg) = Self::parse_arg_values(&argv[start..end], name, expected);
+        // The number of positional arguments met so far.
+        let mut positionals = 0;
 
-            // SAFETY: the validation above guarantees that the capacity of the resulting
-            // parsed args is sufficient.
-            unsafe { args.push(arg).unwrap_unchecked() };
+        for item in argv {
+            let index = parsed.words.len();
 
-            for value in rest.iter() {
-                // SAFETY: the validation above guarantees that the capacity of the resulting
-                // parsed args is sufficient.
-                unsafe { args.push(Arg::Positional(value)).unwrap_unchecked() };
+            parsed
+                .words
+                .push(item.into_word()?)
+                .map_err(|_| Error::OutOfMemory)?;
+
+            // Trailing words are kept verbatim until the end of the input.
+            if let Some(Pending::Defined(_, AtMost::Trailing, _)) = pending {
+                continue;
             }
-        };
 
-        let positional = |args: &mut Vec<_, _>, value| {
-            // SAFETY: the validation above guarantees that the capacity of the resulting
-            // parsed args is sufficient.
-            unsafe { args.push(Arg::Positional(value)).unwrap_unchecked() }
-        };
+            // The end of options (i.e. `--`) closes the pending flag, and is not an argument
+            // itself.
+            if !options_done && parsed.words[index].as_bytes() == b"--" {
+                if let Some(prev) = pending.take() {
+                    parsed.push_pending(prev, index, table);
+                }
+
+                options_done = true;
+                continue;
+            }
+
+            // Once options are done, all the remaining words are positional arguments, up to the
+            // trailing words, if any.
+            if options_done {
+                match table.trailing {
+                    Some(name) if positionals >= table.trailing_after => {
+                        pending = Some(Pending::Defined(name, AtMost::Trailing, index));
+                    }
+                    _ => {
+                        parsed.push(Arg::Positional(index));
+                        positionals += 1;
+                    }
+                }
 
-        let parse_then_push =
-            |state, (index, arg): (usize, &&'a str)| match (state, Token::tokenize(arg)) {
+                continue;
+            }
+
+            // Only keep the flag metadata, so that the word is not borrowed any longer.
+            let word = parsed.words[index].as_bytes();
+            let (next, attached) = match Token::tokenize_bytes_with_value(word) {
+                Some((flag, offset)) => (
+                    Some(Self::check_undefined_argument(&flag, table, index)?),
+                    offset.is_some(),
+                ),
+                None => (None, false),
+            };
+
+            // A value attached to its flag (e.g. `--color=always`) is its only one.
+            if let (Some(next), true) = (next, attached) {
+                if let Some(prev) = pending {
+                    parsed.push_pending(prev, index, table);
+                }
+
+                parsed.push_attached(next, index, table)?;
+                pending = None;
+                continue;
+            }
+
+            pending = match (pending, next) {
                 // A flag has been met, while this new flag occurs, then save the previous one and
                 // keep going on the new flag values.
-                (Some((flag, start)), Token::Flag(next)) => {
-                    let (name, expected) = lookup(&flag);
-                    named(&mut parsed.args, name, expected, (start, index));
-                    Some((next, index + 1))
+                (Some(prev), Some(next)) => {
+                    parsed.push_pending(prev, index, table);
+                    parsed.push_flag(next, table)
+                }
+
+                // A flag has been met and this value is its only one, then save it.
+                (Some(prev @ Pending::Defined(_, AtMost::One, _)), None) => {
+                    parsed.push_pending(prev, index + 1, table);
+                    None
+                }
+
+                // An ignored flag has been met and this value is its only one, so that the next
+                // values are positional arguments.
+                (Some(prev @ Pending::Undefined(_)), None)
+                    if table.undefined == UndefinedPolicy::Ignore =>
+                {
+                    parsed.push_pending(prev, index + 1, table);
+                    None
                 }
 
                 // A flag has been met and this value belong to it, then keep going.
-                (Some(_), Token::Value(_)) => state,
+                (Some(_), None) => pending,
 
                 // No flag has been met and a new one occurs, then keep going on the new flag
                 // values.
-                (None, Token::Flag(flag)) => Some((flag, index + 1)),
+                (None, Some(next)) => parsed.push_flag(next, table),
 
-                // No flag has been met, then this value is a positional argument.
-                (None, Token::Value(value)) => {
-                    positional(&mut parsed.args, value);
-                    None
+                // No flag has been met, then this value is either the first of the trailing
+                // words, once the positional arguments before them have been met, or a
+                // positional argument.
+                (None, None) => match table.trailing {
+                    Some(name) if positionals >= table.trailing_after => {
+                        Some(Pending::Defined(name, AtMost::Trailing, index))
+                    }
+                    _ => {
+                        parsed.push(Arg::Positional(index));
+                        positionals += 1;
+                        options_done = table.mode == ParsingMode::OptionsFirst;
+                        None
+                    }
+                },
+            };
+        }
+
+        if let Some(prev) = pending {
+            parsed.push_pending(prev, parsed.words.len(), table);
+        }
+
+        parsed.sort();
+        parsed.check_possible_values(table)?;
+
+        // Deprecated arguments are still parsed, but reported once each.
+        if let Some(hook) = table.deprecation_hook {
+            for (id, options) in table.options {
+                if let Some(note) = options.deprecated
+                    && parsed.contains(id)
+                {
+                    hook(id, note);
                 }
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Try to parse the input arguments, then take the values of the missing arguments from the
+    /// given provider, if any (see [`ArgOptions::with_setting`]). The command line always takes
+    /// precedence over the provider.
+    pub fn try_parse_with<I, P>(
+        argv: I,
+        table: &'a ArgLookupTable<'static>,
+        provider: &'a P,
+    ) -> Result<Self, Error>
+    where
+        I: IntoIterator,
+        I::Item: IntoWord<W>,
+        P: ValueProvider + ?Sized,
+    {
+        let mut parsed = Self::try_parse_from(argv, table)?;
+
+        for (id, options) in table.options {
+            let Some(key) = options.setting else {
+                continue;
             };
 
-        let last_flag = argv.iter().enumerate().fold(None, parse_then_push);
+            if parsed.contains(id) {
+                continue;
+            }
+
+            if let Some(value) = provider.value_of(key) {
+                // The arguments remain sorted, so that the next ones are still looked up.
+                let arg = Arg::Provided(id, value);
+                let index = parsed.args.partition_point(|x| x.cmp_parsed(&arg).is_lt());
 
-        if let Some((flag, start)) = last_flag {
-            let (name, expected) = lookup(&flag);
-            named(&mut parsed.args, name, expected, (start, argv.len()));
+                parsed
+                    .args
+                    .insert(index, arg)
+                    .map_err(|_| Error::OutOfMemory)?;
+            }
         }
 
+        parsed.check_possible_values(table)?;
+
         Ok(parsed)
     }
 
+    /// Check that the values of the arguments with possible values are among them, so that a
+    /// misspelled value is reported along with the closest possible one.
+    fn check_possible_values(&self, table: &ArgLookupTable<'static>) -> Result<()> {
+        for arg in &self.args {
+            let Some(id) = arg.id() else {
+                continue;
+            };
+
+            let possible = table.options_of(id).possible_values;
+
+            if possible.is_empty() {
+                continue;
+            }
+
+            for value in self.values_in(arg).bytes() {
+                let value = core::str::from_utf8(value).map_err(|_| Error::InvalidValue(None))?;
+
+                if !possible.contains(&value) {
+                    let closest = utils::closest(value, possible.iter().copied());
+                    return Err(Error::InvalidValue(closest));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the source of the value of the argument with the given identifier.
+    pub fn source_of(&self, id: &str) -> ValueSource {
+        match self.first_of(id) {
+            Some(Arg::Provided(..)) => ValueSource::Provider,
+            Some(_) => ValueSource::CommandLine,
+            None => ValueSource::Default,
+        }
+    }
+
+    /// Get the positional arguments as bytes, in order of appearance.
+    pub fn positional_bytes(&self) -> impl Iterator<Item = &[u8]> {
+        self.args.iter().filter_map(|x| match x {
+            Arg::Positional(index) => Some(self.words[*index].as_bytes()),
+            _ => None,
+        })
+    }
+
+    /// Try to get and parse the positional argument at the given index, if any.
+    pub fn try_get_positional<T>(&self, index: usize) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+    {
+        self.positional_bytes()
+            .nth(index)
+            .map(parse_value)
+            .transpose()
+    }
+
+    /// Try to get and parse the positional arguments from the given index. The value can be
+    /// constructed from an iterator.
+    pub fn try_get_positionals<B, T>(&self, from: usize) -> Result<B, Error>
+    where
+        B: FromIterator<T>,
+        T: FromStr,
+    {
+        self.positional_bytes()
+            .skip(from)
+            .map(parse_value)
+            .collect()
+    }
+
+    /// Get the undefined flags and their values as bytes, in order of appearance, if collected
+    /// (see [`UndefinedPolicy::Collect`]).
+    pub fn undefined_bytes(&self) -> impl Iterator<Item = &[u8]> {
+        self.undefined_words().map(Word::as_bytes)
+    }
+
+    fn undefined_words(&self) -> impl Iterator<Item = &W> {
+        self.args
+            .iter()
+            .filter_map(|x| match x {
+                Arg::Undefined(range) => Some(&self.words[range.clone()]),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// Try to parse the collected undefined flags and their values. The value can be constructed
+    /// from an iterator.
+    pub fn try_get_undefined<B, T>(&self) -> Result<B, Error>
+    where
+        B: FromIterator<T>,
+        T: FromStr,
+    {
+        self.undefined_bytes().map(parse_value).collect()
+    }
+
+    /// Get the values of the argument with the given identifier as bytes, if present. The values
+    /// of its first occurrence only are given.
+    pub fn bytes_of(&self, id: &str) -> Option<impl Iterator<Item = &[u8]>> {
+        self.first_of(id).map(|x| self.values_in(x).bytes())
+    }
+
+    /// Try to get the values as bytes, if any, which are not necessarily valid UTF-8 strings
+    /// (e.g. a binary payload). The value can be constructed from an iterator. The values of
+    /// every occurrence of the argument are collected.
+    pub fn try_get_bytes<'b, B>(&'b self, id: &str) -> Result<Option<B>, Error>
+    where
+        B: FromIterator<&'b [u8]>,
+    {
+        if !self.contains(id) {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            self.occurrences_of(id)
+                .iter()
+                .flat_map(|x| self.values_in(x).bytes())
+                .collect(),
+        ))
+    }
+
+    fn values_in(&self, arg: &Arg<'a>) -> Values<'_, W> {
+        let layout = self.layout_of(arg);
+
+        Values::new(&self.words[layout.range])
+            .with_offset(layout.offset)
+            .with_default(layout.default)
+            .with_delimiter(layout.delimiter)
+    }
+
+    /// Get the layout of the values of a named argument, with its options from the table.
+    fn layout_of(&self, arg: &Arg<'a>) -> Layout<'a> {
+        let options = match (self.table, arg.id()) {
+            (Some(table), Some(id)) => table.options_of(id),
+            _ => ArgOptions::new(),
+        };
+
+        match arg {
+            Arg::Named(_, span) => Layout {
+                range: span.range.clone(),
+                // The attached value starts after the first equal sign of its long flag.
+                offset: if span.attached {
+                    let word = self.words[span.range.start].as_bytes();
+                    word.iter().position(|&x| x == b'=').map_or(0, |x| x + 1)
+                } else {
+                    0
+                },
+                // The flag has been given without any value.
+                default: if span.range.is_empty() {
+                    options.default_missing
+                } else {
+                    None
+                },
+                delimiter: options.delimiter,
+            },
+
+            // The provided value is not part of the parsed words.
+            Arg::Provided(_, value) => Layout {
+                range: 0..0,
+                offset: 0,
+                default: Some(value),
+                delimiter: options.delimiter,
+            },
+
+            _ => Layout::default(),
+        }
+    }
+
+    /// Get the first occurrence of the argument with the given identifier, if present.
+    fn first_of(&self, id: &str) -> Option<&Arg<'a>> {
+        self.occurrences_of(id).first()
+    }
+
+    /// Get every occurrence of the argument with the given identifier, which are contiguous
+    /// since the arguments are sorted.
+    fn occurrences_of(&self, id: &str) -> &[Arg<'a>] {
+        let start = self
+            .args
+            .partition_point(|x| x.id().is_some_and(|name| name < id));
+        let end = self
+            .args
+            .partition_point(|x| x.id().is_some_and(|name| name <= id));
+
+        &self.args[start..end]
+    }
+
     /// Check if there exists an argument with the given key (i.e. short or long flag).
     #[inline(always)]
     pub fn contains(&self, id: &str) -> bool {
-        self.args
-            .iter()
-            .any(|arg| matches!(arg, Arg::Named(name, _) if id == *name))
+        !self.occurrences_of(id).is_empty()
     }
 
     /// Get one value for the given flag identifier.
@@ -188,12 +1048,8 @@ impl<'a, const CAPACITY: usize> ParsedArgs<'a, CAPACITY> {
     where
         T: FromStr,
     {
-        if let Some(Arg::Named(_, values)) = self
-            .args
-            .iter()
-            .find(|&x| matches!(x, Arg::Named(name, _) if *name == id))
-        {
-            let mut iter = values.iter();
+        if let Some(arg) = self.first_of(id) {
+            let mut iter = self.values_in(arg).bytes();
 
             let value = if let Some(value) = iter.next() {
                 value
@@ -207,76 +1063,288 @@ impl<'a, const CAPACITY: usize> ParsedArgs<'a, CAPACITY> {
                 return Err(Error::InvalidArgument);
             }
 
-            return value
-                .parse::<T>()
-                // The argument is present and has a value (i.e. Some(Some(_))).
-                .map(Some)
-                .map(Some)
-                // The value cannot be parsed to the target type `T`.
-                .map_err(|_| Error::InvalidArgument);
+            // The argument is present and has a value (i.e. Some(Some(_))), unless it cannot be
+            // parsed to the target type `T`.
+            return parse_value(value).map(Some).map(Some);
         }
 
         // The argument has not been found.
         Ok(None)
     }
 
-    /// Try to get and parse the argument value if any. The value can be constructed from
-    /// an iterator.
+    /// Try to get and parse the argument values if any. The value can be constructed from
+    /// an iterator. The values of every occurrence of the argument are collected (e.g.
+    /// `--channels 1,2 --channels 5`).
     pub fn try_get_many<B, T>(&self, id: &str) -> Result<Option<B>, Error>
     where
         B: FromIterator<T>,
         T: FromStr,
     {
-        if let Some(Arg::Named(_, values)) = self
-            .args
+        if !self.contains(id) {
+            return Ok(None);
+        }
+
+        self.occurrences_of(id)
+            .iter()
+            .flat_map(|x| self.values_in(x).bytes())
+            .map(parse_value)
+            .collect::<Result<B, _>>()
+            .map(Some)
+    }
+
+    /// Try to get and parse exactly `N` argument values if any. The values of every occurrence
+    /// of the argument are collected, as with [`ParsedArgs::try_get_many`], and any other number
+    /// of values is invalid.
+    pub fn try_get_array<T, const N: usize>(&self, id: &str) -> Result<Option<[T; N]>, Error>
+    where
+        T: FromStr,
+    {
+        if !self.contains(id) {
+            return Ok(None);
+        }
+
+        let mut values = Vec::<T, N>::new();
+
+        for value in self
+            .occurrences_of(id)
             .iter()
-            .find(|&x| matches!(x, Arg::Named(name, _) if *name == id))
+            .flat_map(|x| self.values_in(x).bytes())
         {
-            return Ok(Some(
-                values
-                    .iter()
-                    .map(|x| x.parse::<T>())
-                    .collect::<Result<B, _>>()
-                    .map_err(|_| Error::InvalidArgument)?,
-            ));
+            let value = parse_value(value)?;
+            values.push(value).map_err(|_| Error::InvalidArgument)?;
         }
 
-        Ok(None)
+        values
+            .into_array()
+            .map(Some)
+            .map_err(|_| Error::InvalidArgument)
     }
 
-    fn check_capacity(argv: &[&str]) -> Result<()> {
-        if CAPACITY < argv.len() {
-            return Err(Error::OutOfMemory);
+    /// Try to get and parse the `key<separator>value` pairs of the argument, if any. The pairs of
+    /// every occurrence of the argument are collected (e.g. `--set a=1 --set b=2`).
+    pub fn try_get_map<B>(&self, id: &str, separator: char) -> Result<Option<B>, Error>
+    where
+        B: KeyValueMap,
+        B::Key: FromStr,
+        B::Value: FromStr,
+    {
+        if !self.contains(id) {
+            return Ok(None);
         }
-        Ok(())
-    }
 
-    fn check_undefined_argument(argv: &[&str], table: &ArgLookupTable<'_>) -> Result<()> {
-        let undefined = argv
+        let mut map = B::default();
+
+        for pair in self
+            .occurrences_of(id)
             .iter()
-            .map(|&x| Token::tokenize(x))
-            .any(|x| matches!(x, Token::Flag(flag) if table.metadata_of(&flag).is_none()));
+            .flat_map(|x| self.values_in(x).bytes())
+        {
+            let pair = core::str::from_utf8(pair).map_err(|_| Error::InvalidArgument)?;
+            let (key, value) = pair
+                .split_once(separator)
+                .filter(|(key, _)| !key.is_empty())
+                .ok_or(Error::MalformedPair)?;
+
+            let key = key.parse().map_err(|_| Error::InvalidArgument)?;
+            let value = value.parse().map_err(|_| Error::InvalidArgument)?;
+
+            match map.insert_pair(key, value) {
+                Ok(None) => {}
+                Ok(Some(_)) => return Err(Error::DuplicateKey),
+                Err(_) => return Err(Error::OutOfMemory),
+            }
+        }
+
+        Ok(Some(map))
+    }
+
+    fn check_undefined_argument(
+        flag: &Flag<'_>,
+        table: &ArgLookupTable<'static>,
+        index: usize,
+    ) -> Result<Pending<'static>> {
+        match table.try_metadata_of(flag)? {
+            Some((name, expected)) => Ok(Pending::Defined(name, expected, index + 1)),
+            None if table.undefined == UndefinedPolicy::Error => Err(table
+                .suggestion_for(flag)
+                .map_or(Error::UndefinedArgument, Error::MisspelledArgument)),
+            None => Ok(Pending::Undefined(index)),
+        }
+    }
+
+    fn push_flag(&mut self, next: Pending<'a>, table: &ArgLookupTable<'a>) -> Option<Pending<'a>> {
+        match next {
+            // The flag cannot have values, or only attached ones, then save it right away.
+            Pending::Defined(name, expected, start)
+                if expected == AtMost::Zero || table.options_of(name).require_equals =>
+            {
+                self.push_pending(next, start, table);
+                None
+            }
+
+            // The values are delimited within a single word, then only take the next one.
+            Pending::Defined(name, AtMost::Many, start)
+                if table.options_of(name).delimiter.is_some() =>
+            {
+                Some(Pending::Defined(name, AtMost::One, start))
+            }
 
-        if undefined {
-            return Err(Error::UndefinedArgument);
+            _ => Some(next),
+        }
+    }
+
+    fn push_attached(
+        &mut self,
+        next: Pending<'a>,
+        index: usize,
+        table: &ArgLookupTable<'a>,
+    ) -> Result<()> {
+        match next {
+            Pending::Defined(_, AtMost::Zero, _) => return Err(Error::NoValueArgument),
+
+            Pending::Defined(name, _, _) => {
+                let span = Span {
+                    range: index..index + 1,
+                    attached: true,
+                };
+
+                self.push(Arg::Named(name, span));
+            }
+
+            Pending::Undefined(_) => self.push_pending(next, index + 1, table),
         }
 
         Ok(())
     }
 
-    fn parse_arg_values<'b>(
-        argv: &'b [&'b str],
-        name: &'b str,
-        expected: AtMost,
-    ) -> (Values<'b>, Arg<'b>) {
-        match expected {
-            AtMost::Zero => (Values::new(argv), Arg::Named(name, Values::empty())),
-            AtMost::One => {
-                let rest = if argv.len() <= 1 { &[] } else { &argv[1..] };
-                let arg = if argv.is_empty() { &[] } else { &argv[..1] };
-                (Values::new(rest), Arg::Named(name, Values::new(arg)))
+    fn push_pending(&mut self, pending: Pending<'a>, end: usize, table: &ArgLookupTable<'a>) {
+        match pending {
+            Pending::Defined(name, _, start) => self.push(Arg::Named(name, (start..end).into())),
+
+            Pending::Undefined(start) if table.undefined == UndefinedPolicy::Collect => {
+                self.push(Arg::Undefined(start..end))
             }
-            AtMost::Many => (Values::empty(), Arg::Named(name, Values::new(argv))),
+
+            Pending::Undefined(_) => {}
+        }
+    }
+
+    fn sort(&mut self) {
+        self.args.sort_unstable_by(Arg::cmp_parsed);
+    }
+
+    fn push(&mut self, arg: Arg<'a>) {
+        // SAFETY: every argument is made of at least one distinct word, and the words have
+        // already been pushed with a capacity check, so the arguments cannot overflow.
+        unsafe { self.args.push(arg).unwrap_unchecked() }
+    }
+}
+
+impl<'a, const CAPACITY: usize, W> ParsedArgs<'a, CAPACITY, W>
+where
+    W: AsRef<str>,
+{
+    /// Get the positional arguments, in order of appearance.
+    pub fn positionals(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().filter_map(|x| match x {
+            Arg::Positional(index) => Some(self.words[*index].as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Get the undefined flags and their values, in order of appearance, if collected (see
+    /// [`UndefinedPolicy::Collect`]).
+    pub fn undefined(&self) -> impl Iterator<Item = &str> {
+        self.undefined_words().map(AsRef::as_ref)
+    }
+
+    /// Get the values of the argument with the given identifier, if present.
+    pub fn values_of(&self, id: &str) -> Option<Values<'_, W>> {
+        self.first_of(id).map(|x| self.values_in(x))
+    }
+}
+
+/// Parse a value, which must be a valid UTF-8 string.
+fn parse_value<T>(value: &[u8]) -> Result<T>
+where
+    T: FromStr,
+{
+    core::str::from_utf8(value)
+        .ok()
+        .and_then(|x| x.parse::<T>().ok())
+        .ok_or(Error::InvalidArgument)
+}
+
+impl<'a, const CAPACITY: usize> ParsedArgs<'a, CAPACITY, &'a str> {
+    /// Get the values of the argument with the given identifier, if present, borrowed from the
+    /// input rather than from the parsed arguments.
+    pub fn words_of(
+        &self,
+        id: &str,
+    ) -> Option<impl Iterator<Item = &'a str> + use<'_, 'a, CAPACITY>> {
+        self.first_of(id).map(|x| self.words_in(x))
+    }
+
+    /// Try to get the values borrowed from the input, if any. The value can be constructed from
+    /// an iterator. The values of every occurrence of the argument are collected.
+    pub fn try_get_words<B>(&self, id: &str) -> Result<Option<B>, Error>
+    where
+        B: FromIterator<&'a str>,
+    {
+        if !self.contains(id) {
+            return Ok(None);
         }
+
+        Ok(Some(
+            self.occurrences_of(id)
+                .iter()
+                .flat_map(|x| self.words_in(x))
+                .collect(),
+        ))
+    }
+
+    fn words_in(&self, arg: &Arg<'a>) -> impl Iterator<Item = &'a str> + use<'_, 'a, CAPACITY> {
+        let Layout {
+            range,
+            offset,
+            default,
+            delimiter,
+        } = self.layout_of(arg);
+
+        self.words[range]
+            .iter()
+            .copied()
+            .enumerate()
+            .map(move |(i, x)| if i == 0 { &x[offset..] } else { x })
+            .chain(default)
+            .flat_map(move |x| x.split(move |c| Some(c) == delimiter))
     }
 }
+
+/// Layout of the values of a named argument, which is computed when they are read.
+#[derive(Default)]
+struct Layout<'a> {
+    /// The range of the values in the parsed words.
+    range: Range<usize>,
+
+    /// The number of bytes to skip in the first value, if attached to its flag.
+    offset: usize,
+
+    /// The default value, if the flag has been given without any value, or the value given by a
+    /// provider.
+    default: Option<&'a str>,
+
+    /// The delimiter of the values within a single word, if any.
+    delimiter: Option<char>,
+}
+
+/// A flag waiting for its values.
+#[derive(Clone, Copy)]
+enum Pending<'a> {
+    /// A defined flag, with its identifier, the number of expected values, and the index of its
+    /// first value.
+    Defined(&'a str, AtMost, usize),
+
+    /// An undefined flag, with the index of the flag itself.
+    Undefined(usize),
+}
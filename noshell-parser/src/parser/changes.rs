@@ -0,0 +1,35 @@
+//! Changes of arguments.
+
+/// The arguments of a fixed set which are changed by a command line, when it is applied onto
+/// existing values (e.g. settings which should only be persisted if changed). An argument given
+/// with its current value is not changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChangedArgs<'a, const N: usize> {
+    changes: [(&'a str, bool); N],
+}
+
+impl<'a, const N: usize> ChangedArgs<'a, N> {
+    /// Create the changes from the argument identifiers and whether they are changed.
+    pub const fn new(changes: [(&'a str, bool); N]) -> Self {
+        ChangedArgs { changes }
+    }
+
+    /// Check if the argument with the given identifier is changed. Unknown arguments are not.
+    pub fn is_changed(&self, id: &str) -> bool {
+        self.changes.iter().any(|(x, changed)| *x == id && *changed)
+    }
+
+    /// Check if no argument is changed.
+    pub fn is_empty(&self) -> bool {
+        self.changes.iter().all(|(_, changed)| !changed)
+    }
+
+    /// Iterate over the identifiers of the changed arguments.
+    pub fn iter(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.changes
+            .iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(id, _)| *id)
+    }
+}
@@ -0,0 +1,609 @@
+//! Deserialization of parsed arguments with `serde`.
+//!
+//! The parsed arguments are seen as a map, whose keys are the argument identifiers. The values
+//! of an argument are deserialized depending on the destination type: presence-only flags as
+//! booleans, many values as sequences, `key=value` pairs as maps, and a single value as anything
+//! else, which is parsed with [`FromStr`].
+
+use core::fmt::Display;
+use core::str::FromStr;
+
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::parser::ParsedArgs;
+
+/// Defines the possible errors that may occur during deserialization of arguments.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Error {
+    /// An error comes from the parsed arguments.
+    #[error(transparent)]
+    Parser(#[from] crate::parser::Error),
+
+    /// A required field is not given on the command line.
+    #[error("missing field `{0}`")]
+    MissingField(&'static str),
+
+    /// Any other error raised by the destination type. The message is dropped, since it cannot
+    /// be stored without allocation.
+    #[error("cannot deserialize arguments")]
+    Custom,
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(_msg: T) -> Self {
+        Error::Custom
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField(field)
+    }
+}
+
+/// Result type of deserialization.
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// Deserialize a value from the parsed arguments.
+pub fn from_args<'de, T, const CAPACITY: usize, W>(
+    args: &'de ParsedArgs<'_, CAPACITY, W>,
+) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+    W: AsRef<str>,
+{
+    T::deserialize(Deserializer::new(args))
+}
+
+/// Deserializer over the parsed arguments, seen as a map of argument identifiers to values.
+pub struct Deserializer<'de, 'b, const CAPACITY: usize, W> {
+    args: &'de ParsedArgs<'b, CAPACITY, W>,
+}
+
+impl<'de, 'b, const CAPACITY: usize, W> Deserializer<'de, 'b, CAPACITY, W> {
+    /// Create a new deserializer.
+    pub fn new(args: &'de ParsedArgs<'b, CAPACITY, W>) -> Self {
+        Deserializer { args }
+    }
+}
+
+impl<'de, const CAPACITY: usize, W> de::Deserializer<'de> for Deserializer<'de, '_, CAPACITY, W>
+where
+    W: AsRef<str>,
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(ArgsAccess {
+            args: self.args,
+            index: 0,
+            value: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+/// Access to the named arguments, in order of identifier.
+struct ArgsAccess<'de, 'b, const CAPACITY: usize, W> {
+    args: &'de ParsedArgs<'b, CAPACITY, W>,
+    index: usize,
+    value: Option<&'de str>,
+}
+
+impl<'de, const CAPACITY: usize, W> de::MapAccess<'de> for ArgsAccess<'de, '_, CAPACITY, W>
+where
+    W: AsRef<str>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        while let Some(arg) = self.args.args.get(self.index) {
+            self.index += 1;
+
+            let Some(id) = arg.id() else {
+                continue;
+            };
+
+            // Repeated arguments are deserialized at once, on their first occurrence, which
+            // directly precedes the other ones.
+            let seen = self.index >= 2 && self.args.args[self.index - 2].id() == Some(id);
+
+            if !seen {
+                self.value = Some(id);
+                return seed.deserialize(BorrowedStrDeserializer::new(id)).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let id = self.value.take().ok_or(Error::Custom)?;
+
+        seed.deserialize(ArgDeserializer {
+            args: self.args,
+            id,
+        })
+    }
+}
+
+/// Deserializer of the values of every occurrence of an argument.
+struct ArgDeserializer<'de, 'b, const CAPACITY: usize, W> {
+    args: &'de ParsedArgs<'b, CAPACITY, W>,
+    id: &'de str,
+}
+
+impl<'de, const CAPACITY: usize, W> ArgDeserializer<'de, '_, CAPACITY, W>
+where
+    W: AsRef<str>,
+{
+    fn values(&self) -> impl Iterator<Item = &'de str> + use<'de, CAPACITY, W> {
+        let args = self.args;
+
+        args.occurrences_of(self.id)
+            .iter()
+            .flat_map(move |x| args.values_in(x).iter())
+    }
+
+    fn single(&self) -> Result<WordDeserializer<'de>> {
+        let mut values = self.values();
+
+        match (values.next(), values.next()) {
+            (Some(value), None) => Ok(WordDeserializer(value)),
+            _ => Err(crate::parser::Error::InvalidArgument.into()),
+        }
+    }
+}
+
+impl<'de, const CAPACITY: usize, W> de::Deserializer<'de> for ArgDeserializer<'de, '_, CAPACITY, W>
+where
+    W: AsRef<str>,
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut values = self.values();
+
+        match (values.next(), values.next()) {
+            (None, _) => visitor.visit_bool(true),
+            (Some(value), None) => visitor.visit_borrowed_str(value),
+            _ => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // A presence-only flag is true.
+        if self.values().next().is_none() {
+            return visitor.visit_bool(true);
+        }
+
+        self.single()?.deserialize_bool(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // The argument is present, otherwise it would not have been visited.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(de::value::SeqDeserializer::new(
+            self.values().map(WordDeserializer),
+        ))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // The separator is given by the options of the argument, if any.
+        let separator = self
+            .args
+            .table
+            .and_then(|x| x.options_of(self.id).separator())
+            .unwrap_or('=');
+
+        let split = move |word: &'de str| {
+            word.split_once(separator)
+                .filter(|(key, _)| !key.is_empty())
+        };
+
+        // Malformed pairs are reported before visiting, since the map visitor cannot fail on
+        // them.
+        if self.values().any(|x| split(x).is_none()) {
+            return Err(crate::parser::Error::MalformedPair.into());
+        }
+
+        let pairs = self
+            .values()
+            .filter_map(split)
+            .map(|(key, value)| (WordDeserializer(key), WordDeserializer(value)));
+
+        visitor.visit_map(de::value::MapDeserializer::new(pairs))
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_i8(visitor)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_i16(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_i32(visitor)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_i64(visitor)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_i128(visitor)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_u8(visitor)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_u16(visitor)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_u32(visitor)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_u64(visitor)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_u128(visitor)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_f32(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_f64(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_char(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_str(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_string(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.single()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.single()?.deserialize_identifier(visitor)
+    }
+}
+
+/// Deserializer of a single word, which is parsed depending on the destination type.
+#[derive(Clone, Copy)]
+struct WordDeserializer<'de>(&'de str);
+
+impl WordDeserializer<'_> {
+    fn parse<T: FromStr>(self) -> Result<T> {
+        self.0
+            .parse()
+            .map_err(|_| crate::parser::Error::InvalidArgument.into())
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for WordDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for WordDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_bool(self.parse()?)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i8(self.parse()?)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i16(self.parse()?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i32(self.parse()?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.parse()?)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i128(self.parse()?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u8(self.parse()?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u16(self.parse()?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u32(self.parse()?)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u64(self.parse()?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u128(self.parse()?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f32(self.parse()?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f64(self.parse()?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_char(self.parse()?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        // Only unit variants can be given by their name.
+        visitor.visit_enum(BorrowedStrDeserializer::new(self.0))
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::{String, Vec};
+    use serde::Deserialize;
+    use speculoos::prelude::*;
+
+    use super::*;
+    use crate::lexer::Flag;
+    use crate::parser::{ArgLookupTable, ArgOptions, AtMost};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        Fast,
+        Slow,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Config<'a> {
+        #[serde(default)]
+        verbose: bool,
+        port: u16,
+        name: &'a str,
+        mode: Option<Mode>,
+        channels: Vec<u8, 8>,
+        env: Option<heapless::LinearMap<&'a str, String<8>, 4>>,
+    }
+
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Short('v'), "verbose", AtMost::Zero),
+        (Flag::Long("port"), "port", AtMost::One),
+        (Flag::Long("name"), "name", AtMost::One),
+        (Flag::Long("mode"), "mode", AtMost::One),
+        (Flag::Long("channels"), "channels", AtMost::Many),
+        (Flag::Long("env"), "env", AtMost::Many),
+    ]);
+
+    #[test]
+    fn it_should_deserialize_parsed_args() {
+        let argv = &[
+            "-v",
+            "--port",
+            "8080",
+            "--channels",
+            "1",
+            "2",
+            "--name=device",
+            "--mode",
+            "slow",
+            "--channels",
+            "5",
+        ];
+        let args: ParsedArgs<'_, 32> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+        let config = from_args::<Config, 32, _>(&args);
+
+        assert_that!(config).is_ok().matches(|x| {
+            x.verbose
+                && x.port == 8080
+                && x.name == "device"
+                && x.mode == Some(Mode::Slow)
+                && x.channels.as_slice() == [1, 2, 5]
+                && x.env.is_none()
+        });
+    }
+
+    #[test]
+    fn it_should_deserialize_key_value_pairs_as_map() {
+        let argv = &[
+            "--port",
+            "1",
+            "--name",
+            "x",
+            "--channels",
+            "--env",
+            "a=1",
+            "b=2",
+        ];
+        let args: ParsedArgs<'_, 32> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+        let config = from_args::<Config, 32, _>(&args);
+
+        assert_that!(config).is_ok().matches(|x| {
+            !x.verbose
+                && x.channels.is_empty()
+                && x.env
+                    .as_ref()
+                    .is_some_and(|env| env["a"] == "1" && env["b"] == "2")
+        });
+
+        let argv = &["--port", "1", "--name", "x", "--channels", "--env", "a"];
+        let args: ParsedArgs<'_, 32> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+        assert_that!(from_args::<Config, 32, _>(&args))
+            .is_err()
+            .is_equal_to(Error::Parser(crate::parser::Error::MalformedPair));
+    }
+
+    #[test]
+    fn it_should_deserialize_key_value_pairs_with_their_separator() {
+        #[derive(Debug, Deserialize)]
+        struct Config<'a> {
+            #[serde(borrow)]
+            env: heapless::LinearMap<&'a str, String<8>, 4>,
+        }
+
+        static LOOKUP: ArgLookupTable<'_> =
+            ArgLookupTable::new(&[(Flag::Long("env"), "env", AtMost::Many)])
+                .with_options(&[("env", ArgOptions::new().with_separator(':'))]);
+
+        let argv = &["--env", "a:1", "b:c=2"];
+        let args: ParsedArgs<'_, 32> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+        let config = from_args::<Config, 32, _>(&args);
+        assert_that!(config)
+            .is_ok()
+            .matches(|x| x.env["a"] == "1" && x.env["b"] == "c=2");
+
+        let argv = &["--env", "a=1"];
+        let args: ParsedArgs<'_, 32> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+        assert_that!(from_args::<Config, 32, _>(&args))
+            .is_err()
+            .is_equal_to(Error::Parser(crate::parser::Error::MalformedPair));
+    }
+
+    #[test]
+    fn it_should_not_deserialize_missing_or_invalid_args() {
+        let argv = &["--name", "x", "--channels"];
+        let args: ParsedArgs<'_, 32> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+        assert_that!(from_args::<Config, 32, _>(&args))
+            .is_err()
+            .is_equal_to(Error::MissingField("port"));
+
+        let argv = &["--port", "x", "--name", "x", "--channels"];
+        let args: ParsedArgs<'_, 32> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+        assert_that!(from_args::<Config, 32, _>(&args))
+            .is_err()
+            .is_equal_to(Error::Parser(crate::parser::Error::InvalidArgument));
+    }
+}
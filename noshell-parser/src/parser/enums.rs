@@ -0,0 +1,17 @@
+//! Enumerations of values.
+
+/// An enumeration whose values are given by name on the command line (e.g. `--parity even`),
+/// usually derived with `#[derive(ValueEnum)]`.
+///
+/// The names are known ahead, so that they can be listed in the help and the completions (see
+/// [`ArgInfo::with_possible_values`](crate::parser::ArgInfo::with_possible_values)).
+pub trait ValueEnum: Sized + 'static {
+    /// The names of the values, in order of declaration.
+    const NAMES: &'static [&'static str];
+
+    /// Get the value with the given name, if any.
+    fn from_name(name: &str) -> Option<Self>;
+
+    /// Get the name of the value.
+    fn name(&self) -> &'static str;
+}
@@ -0,0 +1,232 @@
+//! Description of arguments.
+
+use crate::lexer::Flag;
+use crate::parser::{ArgLookupTable, Error};
+use crate::utils;
+
+/// Description of an argument, which is used to document it or to ask for it interactively when
+/// missing on the command line.
+#[derive(Debug, Clone, Copy)]
+pub struct ArgInfo<'a> {
+    id: &'a str,
+    flag: Option<Flag<'a>>,
+    help: &'a str,
+    required: bool,
+    secret: bool,
+    hidden: bool,
+    deprecated: Option<&'a str>,
+    possible_values: &'a [&'a str],
+    validator: Option<fn(&str) -> bool>,
+}
+
+impl<'a> ArgInfo<'a> {
+    /// Create the description of the argument with the given identifier.
+    pub const fn new(id: &'a str) -> Self {
+        ArgInfo {
+            id,
+            flag: None,
+            help: "",
+            required: false,
+            secret: false,
+            hidden: false,
+            deprecated: None,
+            possible_values: &[],
+            validator: None,
+        }
+    }
+
+    /// Set the flag which is written on the command line to give the argument. Positional
+    /// arguments have no flag.
+    pub const fn with_flag(mut self, flag: Flag<'a>) -> Self {
+        self.flag = Some(flag);
+        self
+    }
+
+    /// Set the help, which is usually the documentation of the field.
+    pub const fn with_help(mut self, help: &'a str) -> Self {
+        self.help = help;
+        self
+    }
+
+    /// Set whether the argument must be given.
+    pub const fn with_required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Set whether the value is secret, so that it should not be echoed when asked for.
+    pub const fn with_secret(mut self, secret: bool) -> Self {
+        self.secret = secret;
+        self
+    }
+
+    /// Set whether the argument is hidden from the help and the completions (e.g. a factory
+    /// only flag). It is still parsed.
+    pub const fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Set the deprecation note of the argument (e.g. `use --foo`).
+    pub const fn with_deprecated(mut self, note: &'a str) -> Self {
+        self.deprecated = Some(note);
+        self
+    }
+
+    /// Set the possible values of the argument (e.g. the names of a [`ValueEnum`]), which are
+    /// listed in the help and the completions.
+    ///
+    /// [`ValueEnum`]: crate::parser::ValueEnum
+    pub const fn with_possible_values(mut self, values: &'a [&'a str]) -> Self {
+        self.possible_values = values;
+        self
+    }
+
+    /// Set the validator of a single value (e.g. it can be converted to the field type).
+    pub const fn with_validator(mut self, validator: fn(&str) -> bool) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Get the argument identifier.
+    pub const fn id(&self) -> &'a str {
+        self.id
+    }
+
+    /// Get the flag of the argument, if any.
+    pub const fn flag(&self) -> Option<Flag<'a>> {
+        self.flag
+    }
+
+    /// Get the help of the argument, which is empty if undocumented.
+    pub const fn help(&self) -> &'a str {
+        self.help
+    }
+
+    /// Get whether the argument must be given.
+    pub const fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Get whether the value is secret.
+    pub const fn is_secret(&self) -> bool {
+        self.secret
+    }
+
+    /// Get whether the argument is hidden.
+    pub const fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Get the deprecation note of the argument, if deprecated.
+    pub const fn deprecated(&self) -> Option<&'a str> {
+        self.deprecated
+    }
+
+    /// Get the possible values of the argument, which is empty if any value is accepted.
+    pub const fn possible_values(&self) -> &'a [&'a str] {
+        self.possible_values
+    }
+
+    /// Check if a single value is valid. Any value is valid without validator.
+    pub fn validate(&self, value: &str) -> bool {
+        self.validator.is_none_or(|f| f(value))
+    }
+}
+
+/// Description of a command, with its arguments and its subcommands, which is used to document
+/// it (e.g. to generate its manual or its completions on a host).
+#[derive(Debug, Clone, Copy)]
+pub struct CommandInfo<'a> {
+    name: &'a str,
+    about: &'a str,
+    args: &'a [ArgInfo<'a>],
+    table: Option<&'a ArgLookupTable<'a>>,
+    subcommands: &'a [CommandInfo<'a>],
+}
+
+impl<'a> CommandInfo<'a> {
+    /// Create the description of the command with the given name and arguments (e.g. the
+    /// `ARGS` of a derived parser).
+    pub const fn new(name: &'a str, args: &'a [ArgInfo<'a>]) -> Self {
+        CommandInfo {
+            name,
+            about: "",
+            args,
+            table: None,
+            subcommands: &[],
+        }
+    }
+
+    /// Set the description of what the command does.
+    pub const fn with_about(mut self, about: &'a str) -> Self {
+        self.about = about;
+        self
+    }
+
+    /// Set the look-up table of the arguments (e.g. the `lookup_table()` of a derived parser),
+    /// which gives all their flags, including their aliases. Otherwise, only their own flag is
+    /// known.
+    pub const fn with_lookup_table(mut self, table: &'a ArgLookupTable<'a>) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    /// Set the subcommands, which are given by name right after the command.
+    pub const fn with_subcommands(mut self, subcommands: &'a [CommandInfo<'a>]) -> Self {
+        self.subcommands = subcommands;
+        self
+    }
+
+    /// Get the command name.
+    pub const fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Get the description of the command, which is empty if undocumented.
+    pub const fn about(&self) -> &'a str {
+        self.about
+    }
+
+    /// Get the arguments of the command, including the hidden ones.
+    pub const fn args(&self) -> &'a [ArgInfo<'a>] {
+        self.args
+    }
+
+    /// Get the subcommands.
+    pub const fn subcommands(&self) -> &'a [CommandInfo<'a>] {
+        self.subcommands
+    }
+
+    /// Get all the flags of an argument of the command, which are looked up in its table if any.
+    pub fn flags_of(&self, arg: &ArgInfo<'a>) -> impl Iterator<Item = Flag<'a>> + 'a {
+        let flags = self.table.map(|x| x.flags_of(arg.id()));
+        let own = arg.flag().filter(|_| flags.is_none());
+
+        flags.into_iter().flatten().chain(own)
+    }
+
+    /// Iterate over the arguments which are not hidden.
+    pub fn visible_args(&self) -> impl Iterator<Item = &'a ArgInfo<'a>> + 'a {
+        self.args.iter().filter(|x| !x.is_hidden())
+    }
+}
+
+impl CommandInfo<'static> {
+    /// Find the subcommand with the given name, or the only one starting with it, so that it can
+    /// be dispatched. Otherwise, fail with [`Error::AmbiguousCommand`] on an ambiguous prefix, or
+    /// with [`Error::UnknownCommand`], along with the closest subcommand name, if any.
+    pub fn find_subcommand(&self, name: &str) -> Result<&'static CommandInfo<'static>, Error> {
+        let subcommands = self.subcommands;
+        let names = || subcommands.iter().map(|x| x.name);
+
+        let found = utils::find_by_prefix(name, names())
+            .map_err(Error::AmbiguousCommand)?
+            .ok_or_else(|| Error::UnknownCommand(utils::closest(name, names())))?;
+
+        subcommands
+            .iter()
+            .find(|x| x.name == found)
+            .ok_or(Error::UnknownCommand(None))
+    }
+}
@@ -0,0 +1,50 @@
+//! Maps.
+
+use core::hash::{BuildHasher, Hash};
+
+use heapless::{IndexMap, LinearMap};
+
+/// Map of key-value pairs, which is filled by the parser from arguments like `key=value`.
+pub trait KeyValueMap: Default {
+    /// The type of the keys.
+    type Key;
+
+    /// The type of the values.
+    type Value;
+
+    /// Insert a pair, and return the previous value of the key if any. On insufficient space,
+    /// the pair is given back.
+    #[allow(clippy::type_complexity)]
+    fn insert_pair(
+        &mut self,
+        key: Self::Key,
+        value: Self::Value,
+    ) -> Result<Option<Self::Value>, (Self::Key, Self::Value)>;
+}
+
+impl<K, V, S, const N: usize> KeyValueMap for IndexMap<K, V, S, N>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    type Key = K;
+    type Value = V;
+
+    #[inline(always)]
+    fn insert_pair(&mut self, key: K, value: V) -> Result<Option<V>, (K, V)> {
+        self.insert(key, value)
+    }
+}
+
+impl<K, V, const N: usize> KeyValueMap for LinearMap<K, V, N>
+where
+    K: Eq,
+{
+    type Key = K;
+    type Value = V;
+
+    #[inline(always)]
+    fn insert_pair(&mut self, key: K, value: V) -> Result<Option<V>, (K, V)> {
+        self.insert(key, value)
+    }
+}
@@ -0,0 +1,90 @@
+//! Sources of values.
+
+/// Defines where the value of an argument comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ValueSource {
+    /// The argument is missing, so that its default value is used.
+    #[default]
+    Default,
+
+    /// The value is given by a provider (e.g. settings persisted in flash).
+    Provider,
+
+    /// The value is given on the command line.
+    CommandLine,
+}
+
+/// Provider of values for the arguments that are missing on the command line (e.g. settings
+/// persisted in flash). The arguments are mapped to the provider keys with
+/// [`ArgOptions::with_setting`](crate::parser::ArgOptions::with_setting).
+///
+/// The value is parsed as if it were given on the command line right after the flag.
+pub trait ValueProvider {
+    /// Get the value of the setting with the given key, if any.
+    fn value_of(&self, key: &str) -> Option<&str>;
+}
+
+/// No value is provided.
+impl ValueProvider for () {
+    #[inline(always)]
+    fn value_of(&self, _key: &str) -> Option<&str> {
+        None
+    }
+}
+
+/// The values are looked up by key in a list of key-value pairs.
+impl ValueProvider for [(&str, &str)] {
+    fn value_of(&self, key: &str) -> Option<&str> {
+        self.iter()
+            .find(|(x, _)| *x == key)
+            .map(|(_, value)| *value)
+    }
+}
+
+impl<const N: usize> ValueProvider for [(&str, &str); N] {
+    #[inline(always)]
+    fn value_of(&self, key: &str) -> Option<&str> {
+        self.as_slice().value_of(key)
+    }
+}
+
+impl<T> ValueProvider for &T
+where
+    T: ValueProvider + ?Sized,
+{
+    #[inline(always)]
+    fn value_of(&self, key: &str) -> Option<&str> {
+        (**self).value_of(key)
+    }
+}
+
+/// The sources of the values of a fixed set of arguments, which remain available once the parsed
+/// arguments are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ValueSources<'a, const N: usize> {
+    sources: [(&'a str, ValueSource); N],
+}
+
+impl<'a, const N: usize> ValueSources<'a, N> {
+    /// Create the sources from the argument identifiers and their sources.
+    pub const fn new(sources: [(&'a str, ValueSource); N]) -> Self {
+        ValueSources { sources }
+    }
+
+    /// Get the source of the argument with the given identifier. Unknown arguments have their
+    /// default value.
+    pub fn source_of(&self, id: &str) -> ValueSource {
+        self.sources
+            .iter()
+            .find(|(x, _)| *x == id)
+            .map(|(_, source)| *source)
+            .unwrap_or_default()
+    }
+
+    /// Iterate over the argument identifiers and their sources.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, ValueSource)> + '_ {
+        self.sources.iter().copied()
+    }
+}
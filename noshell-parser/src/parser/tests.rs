@@ -7,62 +7,147 @@ const PARSED_VALUES_CAPACITY: usize = 8;
 
 #[test]
 fn it_should_parse_arg_values_with_missing_value() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)]);
+
     let argv = &["-f"];
-    let (rest, arg) =
-        ParsedArgs::<'_, PARSED_ARG_CAPACITY>::parse_arg_values(&[], "field", AtMost::One);
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
 
-    assert_that!(rest).is_equal_to(Values::empty());
-    assert_that!(arg).is_equal_to(Arg::Named("field", Values::new(&argv[1..])));
-    assert_that!(arg)
-        .matches(|x| matches!(x, Arg::Named("field", values) if values.iter().next().is_none()));
+    assert_that!(args.args.as_slice()).is_equal_to([Arg::Named("field", (1..1).into())].as_slice());
+    assert_that!(args.values_of("field"))
+        .is_some()
+        .matches(|values| values.iter().next().is_none());
 }
 
 #[test]
 fn it_should_parse_arg_values_with_missing_many_values() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::Many)]);
+
     let argv = &["-f"];
-    let (rest, arg) =
-        ParsedArgs::<'_, PARSED_ARG_CAPACITY>::parse_arg_values(&[], "field", AtMost::Many);
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
 
-    assert_that!(rest).is_equal_to(Values::empty());
-    assert_that!(arg).is_equal_to(Arg::Named("field", Values::new(&argv[1..])));
-    assert_that!(arg)
-        .matches(|x| matches!(x, Arg::Named("field", values) if values.iter().next().is_none()));
+    assert_that!(args.args.as_slice()).is_equal_to([Arg::Named("field", (1..1).into())].as_slice());
+    assert_that!(args.values_of("field"))
+        .is_some()
+        .matches(|values| values.iter().next().is_none());
 }
 
 #[test]
 fn it_should_parse_arg_values_with_value() {
-    let argv = &["-f", "42"];
-    let (rest, arg) =
-        ParsedArgs::<'_, PARSED_ARG_CAPACITY>::parse_arg_values(&argv[1..], "field", AtMost::One);
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)]);
+
+    let argv = &["-f", "42", "24"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
 
-    assert_that!(rest).is_equal_to(Values::new(&argv[2..]));
-    assert_that!(arg).is_equal_to(Arg::Named("field", Values::new(&argv[1..])));
-    assert_that!(arg)
-        .matches(|x| matches!(x, Arg::Named("field", values) if values.iter().next().is_some()));
+    assert_that!(args.args.as_slice())
+        .is_equal_to([Arg::Named("field", (1..2).into()), Arg::Positional(2)].as_slice());
+    assert_that!(args.values_of("field"))
+        .is_some()
+        .is_equal_to(Values::new(&argv[1..2]));
 }
 
 #[test]
 fn it_should_parse_arg_values_with_single_many_values() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::Many)]);
+
     let argv = &["-f", "42"];
-    let (rest, arg) =
-        ParsedArgs::<'_, PARSED_ARG_CAPACITY>::parse_arg_values(&[], "field", AtMost::Many);
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
 
-    assert_that!(rest).is_equal_to(Values::empty());
-    assert_that!(arg).is_equal_to(Arg::Named("field", Values::new(&argv[2..])));
-    assert_that!(arg)
-        .matches(|x| matches!(x, Arg::Named("field", values) if values.iter().next().is_none()));
+    assert_that!(args.args.as_slice()).is_equal_to([Arg::Named("field", (1..2).into())].as_slice());
+    assert_that!(args.values_of("field"))
+        .is_some()
+        .matches(|values| values.iter().count() == 1);
 }
 
 #[test]
 fn it_should_parse_arg_values_with_many_value() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Short('f'), "field", AtMost::Many),
+        (Flag::Short('g'), "other", AtMost::Zero),
+    ]);
+
+    let argv = &["-f", "42", "24", "-g", "12"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.args.as_slice()).is_equal_to(
+        [
+            Arg::Named("field", (1..3).into()),
+            Arg::Named("other", (4..4).into()),
+            Arg::Positional(4),
+        ]
+        .as_slice(),
+    );
+    assert_that!(args.values_of("field"))
+        .is_some()
+        .matches(|values| values.iter().count() > 1);
+}
+
+#[test]
+fn it_should_parse_from_iterator_of_owned_words() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)]);
+
+    let argv = ["-f", "42"]
+        .into_iter()
+        .map(|x| heapless::String::<8>::try_from(x).unwrap());
+
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY, heapless::String<8>> =
+        ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.try_get_one::<u32>("field"))
+        .is_ok()
+        .is_some()
+        .is_some()
+        .is_equal_to(42);
+}
+
+#[test]
+fn it_should_parse_from_iterator_of_results() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)]);
+
+    let argv: [Result<&str, ()>; 2] = [Ok("-f"), Ok("42")];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_from(argv, &LOOKUP);
+
+    assert_that!(args.map(|x| x.try_get_one::<u32>("field")))
+        .is_ok()
+        .is_ok()
+        .is_some()
+        .is_some()
+        .is_equal_to(42);
+
+    let argv: [Result<&str, ()>; 2] = [Ok("-f"), Err(())];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_from(argv, &LOOKUP);
+
+    assert_that!(args.map(|_| ())).is_err_containing(Error::InvalidInput);
+}
+
+#[test]
+fn it_should_fail_on_undefined_argument() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)]);
+
+    let argv = &["-f", "42", "-g"];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_from(argv, &LOOKUP);
+
+    assert_that!(args.map(|_| ())).is_err_containing(Error::UndefinedArgument);
+}
+
+#[test]
+fn it_should_fail_on_insufficient_capacity() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::Many)]);
+
     let argv = &["-f", "42", "24"];
-    let (rest, arg) =
-        ParsedArgs::<'_, PARSED_ARG_CAPACITY>::parse_arg_values(&argv[1..], "field", AtMost::Many);
+    let args: Result<ParsedArgs<'_, 2>, _> = ParsedArgs::try_parse_from(argv, &LOOKUP);
 
-    assert_that!(rest).is_equal_to(Values::new(&argv[3..]));
-    assert_that!(arg).is_equal_to(Arg::Named("field", Values::new(&argv[1..])));
-    assert_that!(arg)
-        .matches(|x| matches!(x, Arg::Named("field", values) if values.iter().count() > 1));
+    assert_that!(args.map(|_| ())).is_err_containing(Error::OutOfMemory);
 }
 
 #[test]
@@ -144,3 +229,701 @@ fn it_should_parse_valid_many_value() {
         .is_some()
         .matches(|x| x.iter().sum::<u32>() == 84);
 }
+
+#[test]
+fn it_should_parse_array_value() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::Many)]);
+
+    let argv = &["-f", "1", "2", "-f", "3"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.try_get_array::<u32, 3>("field"))
+        .is_ok()
+        .is_some()
+        .is_equal_to([1, 2, 3]);
+
+    assert_that!(args.try_get_array::<u32, 2>("field")).is_err_containing(Error::InvalidArgument);
+    assert_that!(args.try_get_array::<u32, 4>("field")).is_err_containing(Error::InvalidArgument);
+    assert_that!(args.try_get_array::<u32, 4>("other"))
+        .is_ok()
+        .is_none();
+}
+
+#[test]
+fn it_should_ignore_undefined_arguments() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)])
+            .with_undefined(UndefinedPolicy::Ignore);
+
+    let argv = &["--baud", "9600", "-f", "42", "-x"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.args.as_slice()).is_equal_to([Arg::Named("field", (3..4).into())].as_slice());
+    assert_that!(args.undefined().count()).is_equal_to(0);
+}
+
+#[test]
+fn it_should_only_skip_first_value_of_ignored_arguments() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)])
+            .with_undefined(UndefinedPolicy::Ignore);
+
+    let argv = &["--unknown", "a", "b", "-f", "42"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.args.as_slice())
+        .is_equal_to([Arg::Named("field", (4..5).into()), Arg::Positional(2)].as_slice());
+}
+
+#[test]
+fn it_should_end_values_of_collected_arguments_at_end_of_options() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)])
+            .with_undefined(UndefinedPolicy::Collect);
+
+    // The undefined flag takes all the following values, up to the end of options.
+    let argv = &["--unknown", "a", "b"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+    assert_that!(args.positionals().count()).is_equal_to(0);
+
+    let argv = &["--unknown", "a", "--", "b", "-f"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(
+        args.undefined()
+            .collect::<Vec<_, PARSED_VALUES_CAPACITY>>()
+            .as_slice()
+    )
+    .is_equal_to(["--unknown", "a"].as_slice());
+    assert_that!(args.contains("field")).is_false();
+    assert_that!(args.args.as_slice())
+        .matches(|x| x.ends_with(&[Arg::Positional(3), Arg::Positional(4)]));
+}
+
+#[test]
+fn it_should_collect_undefined_arguments() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('f'), "field", AtMost::One)])
+            .with_undefined(UndefinedPolicy::Collect);
+
+    let argv = &["--baud", "9600", "8N1", "-f", "42", "-x"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.try_get_one::<u32>("field"))
+        .is_ok()
+        .is_some()
+        .is_some()
+        .is_equal_to(42);
+
+    assert_that!(
+        args.undefined()
+            .collect::<Vec<_, PARSED_VALUES_CAPACITY>>()
+            .as_slice()
+    )
+    .is_equal_to(["--baud", "9600", "8N1", "-x"].as_slice());
+
+    assert_that!(args.try_get_undefined::<Vec<heapless::String<8>, PARSED_VALUES_CAPACITY>, _>())
+        .is_ok()
+        .matches(|x| x.len() == 4);
+}
+
+#[test]
+fn it_should_parse_trailing_flag_values_verbatim() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Short('n'), "count", AtMost::One),
+        (Flag::Long("exec"), "exec", AtMost::Trailing),
+    ]);
+
+    let argv = &["-n", "5", "--exec", "echo", "-n", "--unknown", "42"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.args.as_slice()).is_equal_to(
+        [
+            Arg::Named("count", (1..2).into()),
+            Arg::Named("exec", (3..7).into()),
+        ]
+        .as_slice(),
+    );
+    assert_that!(
+        args.words_of("exec")
+            .map(|x| x.collect::<Vec<_, PARSED_VALUES_CAPACITY>>())
+    )
+    .is_some()
+    .matches(|x| x.as_slice() == ["echo", "-n", "--unknown", "42"]);
+}
+
+#[test]
+fn it_should_parse_trailing_positional_values_verbatim() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('n'), "count", AtMost::One)]).with_trailing("command");
+
+    let argv = &["-n", "5", "echo", "-n", "42"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.args.as_slice()).is_equal_to(
+        [
+            Arg::Named("command", (2..5).into()),
+            Arg::Named("count", (1..2).into()),
+        ]
+        .as_slice(),
+    );
+    assert_that!(args.values_of("command"))
+        .is_some()
+        .is_equal_to(Values::new(&argv[2..]));
+}
+
+#[test]
+fn it_should_parse_trailing_values_after_positionals() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Short('n'), "count", AtMost::One)])
+            .with_trailing("command")
+            .with_trailing_after(1);
+
+    let argv = &["5", "echo", "--x", "42"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.args.as_slice())
+        .is_equal_to([Arg::Named("command", (1..4).into()), Arg::Positional(0)].as_slice());
+    assert_that!(args.positionals().collect::<Vec<_, 4>>().as_slice())
+        .is_equal_to(["5"].as_slice());
+    assert_that!(args.values_of("command"))
+        .is_some()
+        .is_equal_to(Values::new(&argv[1..]));
+}
+
+#[test]
+fn it_should_suggest_closest_flag_on_undefined_argument() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Long("retries"), "retries", AtMost::One),
+        (Flag::Long("verbose"), "verbose", AtMost::Zero),
+    ]);
+
+    let argv = &["--retires", "3"];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_from(argv, &LOOKUP);
+
+    let expected = Error::MisspelledArgument(Flag::Long("retries"));
+    assert_that!(args.map(|_| ())).is_err_containing(&expected);
+
+    let mut message = heapless::String::<64>::new();
+    core::fmt::write(&mut message, format_args!("{}", expected)).unwrap();
+    assert_that!(message.as_str()).is_equal_to("undefined argument, did you mean `--retries`?");
+}
+
+#[test]
+fn it_should_suggest_closest_possible_value_on_invalid_value() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Long("modes"), "modes", AtMost::Many),
+        (Flag::Long("parity"), "parity", AtMost::One),
+    ])
+    .with_options(&[
+        (
+            "modes",
+            ArgOptions::new()
+                .with_delimiter(',')
+                .with_possible_values(&["fast", "safe"]),
+        ),
+        (
+            "parity",
+            ArgOptions::new()
+                .with_possible_values(&["none", "even", "odd"])
+                .with_setting("uart.parity"),
+        ),
+    ])
+    .with_sorted();
+
+    let argv = &["--parity", "even", "--modes", "fast,safe"];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_from(argv, &LOOKUP);
+    assert_that!(args).is_ok();
+
+    let argv = &["--modes", "fast,sade"];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_from(argv, &LOOKUP);
+
+    let expected = Error::InvalidValue(Some("safe"));
+    assert_that!(args.map(|_| ())).is_err_containing(&expected);
+
+    let mut message = heapless::String::<64>::new();
+    core::fmt::write(&mut message, format_args!("{}", expected)).unwrap();
+    assert_that!(message.as_str()).is_equal_to("invalid value, did you mean `safe`?");
+
+    // The provided values are checked alike.
+    let settings = [("uart.parity", "xyz")];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_with(&[] as &[&str], &LOOKUP, &settings);
+    assert_that!(args.map(|_| ())).is_err_containing(Error::InvalidValue(None));
+}
+
+#[test]
+fn it_should_suggest_closest_subcommand_on_unknown_command() {
+    static MODEM: CommandInfo<'static> = CommandInfo::new("modem", &[]).with_subcommands(&[
+        CommandInfo::new("reset", &[]),
+        CommandInfo::new("status", &[]),
+    ]);
+
+    assert_that!(MODEM.find_subcommand("status").map(|x| x.name()))
+        .is_ok()
+        .is_equal_to("status");
+    assert_that!(MODEM.find_subcommand("rest").map(|_| ()))
+        .is_err_containing(Error::UnknownCommand(Some("reset")));
+    assert_that!(MODEM.find_subcommand("flash").map(|_| ()))
+        .is_err_containing(Error::UnknownCommand(None));
+}
+
+#[test]
+fn it_should_find_subcommand_by_unique_prefix() {
+    static MODEM: CommandInfo<'static> = CommandInfo::new("modem", &[]).with_subcommands(&[
+        CommandInfo::new("reset", &[]),
+        CommandInfo::new("reset-all", &[]),
+        CommandInfo::new("restart", &[]),
+        CommandInfo::new("status", &[]),
+    ]);
+
+    assert_that!(MODEM.find_subcommand("st").map(|x| x.name()))
+        .is_ok()
+        .is_equal_to("status");
+    assert_that!(MODEM.find_subcommand("resta").map(|x| x.name()))
+        .is_ok()
+        .is_equal_to("restart");
+
+    // An exact name is never ambiguous, even if it prefixes other names.
+    assert_that!(MODEM.find_subcommand("reset").map(|x| x.name()))
+        .is_ok()
+        .is_equal_to("reset");
+
+    let mut candidates = utils::Candidates::new();
+    candidates.push("reset");
+    candidates.push("reset-all");
+    candidates.push("restart");
+
+    let expected = Error::AmbiguousCommand(candidates);
+    assert_that!(MODEM.find_subcommand("res").map(|_| ())).is_err_containing(&expected);
+
+    let mut message = heapless::String::<64>::new();
+    core::fmt::write(&mut message, format_args!("{}", expected)).unwrap();
+    assert_that!(message.as_str())
+        .is_equal_to("ambiguous command, could be `reset`, `reset-all`, `restart`");
+}
+
+#[test]
+fn it_should_match_unique_prefix_of_long_flag() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Long("verbose"), "verbose", AtMost::Zero),
+        (Flag::Long("version"), "version", AtMost::Zero),
+        (Flag::Long("retries"), "retries", AtMost::One),
+    ])
+    .with_matching(FlagMatching::Prefix);
+
+    let argv = &["--verb", "--ret", "3"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.contains("verbose")).is_true();
+    assert_that!(args.try_get_one::<u32>("retries"))
+        .is_ok()
+        .is_some()
+        .is_some()
+        .is_equal_to(3);
+}
+
+#[test]
+fn it_should_fail_on_ambiguous_prefix_of_long_flag() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Long("verbose"), "verbose", AtMost::Zero),
+        (Flag::Long("version"), "version", AtMost::Zero),
+    ])
+    .with_matching(FlagMatching::Prefix);
+
+    let argv = &["--ver"];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_from(argv, &LOOKUP);
+
+    assert_that!(args.map(|_| ())).is_err().matches(|x| {
+        matches!(x, Error::AmbiguousArgument(candidates)
+            if candidates.iter().eq([Flag::Long("verbose"), Flag::Long("version")]))
+    });
+}
+
+#[test]
+fn it_should_not_match_prefix_of_long_flag_by_default() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Long("verbose"), "verbose", AtMost::Zero)]);
+
+    let argv = &["--verb"];
+    let args: Result<ParsedArgs<'_, PARSED_ARG_CAPACITY>, _> =
+        ParsedArgs::try_parse_from(argv, &LOOKUP);
+
+    assert_that!(args.map(|_| ()))
+        .is_err_containing(Error::MisspelledArgument(Flag::Long("verbose")));
+}
+
+#[test]
+fn it_should_parse_intermixed_flags_and_positionals() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Short('v'), "verbose", AtMost::Zero),
+        (Flag::Short('n'), "count", AtMost::One),
+    ]);
+
+    let argv = &["-n", "5", "echo", "-v", "hello"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.contains("verbose")).is_true();
+    assert_that!(
+        args.positionals()
+            .collect::<Vec<_, PARSED_VALUES_CAPACITY>>()
+            .as_slice()
+    )
+    .is_equal_to(["echo", "hello"].as_slice());
+}
+
+#[test]
+fn it_should_stop_parsing_flags_at_first_positional_with_options_first() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Short('v'), "verbose", AtMost::Zero),
+        (Flag::Short('n'), "count", AtMost::One),
+    ])
+    .with_mode(ParsingMode::OptionsFirst);
+
+    let argv = &["-v", "-n", "5", "echo", "-v", "--unknown", "hello"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.contains("verbose")).is_true();
+    assert_that!(args.try_get_one::<u32>("count"))
+        .is_ok()
+        .is_some()
+        .is_some()
+        .is_equal_to(5);
+    assert_that!(
+        args.positionals()
+            .collect::<Vec<_, PARSED_VALUES_CAPACITY>>()
+            .as_slice()
+    )
+    .is_equal_to(["echo", "-v", "--unknown", "hello"].as_slice());
+}
+
+#[test]
+fn it_should_parse_value_attached_to_long_flag() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Long("color"), "color", AtMost::One),
+        (Flag::Long("verbose"), "verbose", AtMost::Zero),
+    ]);
+
+    let argv = &["--color=always", "file"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.words_of("color").and_then(|mut x| x.next()))
+        .is_some()
+        .is_equal_to("always");
+    assert_that!(args.positionals().next())
+        .is_some()
+        .is_equal_to("file");
+
+    let argv = &["--verbose=yes"];
+    assert_that!(ParsedArgs::<'_, PARSED_ARG_CAPACITY>::try_parse_from(
+        argv, &LOOKUP
+    ))
+    .is_err()
+    .is_equal_to(Error::NoValueArgument);
+}
+
+#[test]
+fn it_should_only_bind_value_with_equals_if_required() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Long("color"), "color", AtMost::One)]).with_options(&[(
+            "color",
+            ArgOptions::new()
+                .with_require_equals(true)
+                .with_default_missing("auto"),
+        )]);
+
+    let argv = &["--color", "always"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.words_of("color").and_then(|mut x| x.next()))
+        .is_some()
+        .is_equal_to("auto");
+    assert_that!(args.positionals().next())
+        .is_some()
+        .is_equal_to("always");
+
+    let argv = &["--color=never"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.words_of("color").and_then(|mut x| x.next()))
+        .is_some()
+        .is_equal_to("never");
+
+    let argv: &[&str] = &[];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.contains("color")).is_false();
+}
+
+#[test]
+fn it_should_parse_key_value_pairs_of_repeated_flag() {
+    use heapless::index_map::FnvIndexMap;
+    use heapless::{LinearMap, String};
+
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Long("set"), "set", AtMost::Many)]);
+
+    let argv = &["--set", "a=1", "b=2", "--set", "c:d=3"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    let map = args.try_get_map::<FnvIndexMap<String<8>, u32, 4>>("set", '=');
+    assert_that!(map).is_ok().is_some().matches(|x| {
+        x.iter()
+            .map(|(k, v)| (k.as_str(), *v))
+            .eq([("a", 1), ("b", 2), ("c:d", 3)])
+    });
+
+    let map = args.try_get_map::<LinearMap<String<8>, String<8>, 4>>("set", ':');
+    assert_that!(map).is_err().is_equal_to(Error::MalformedPair);
+
+    let map = args.try_get_map::<LinearMap<String<8>, u32, 2>>("set", '=');
+    assert_that!(map).is_err().is_equal_to(Error::OutOfMemory);
+
+    let map = args.try_get_map::<LinearMap<String<8>, u32, 4>>("other", '=');
+    assert_that!(map).is_ok().is_none();
+}
+
+#[test]
+fn it_should_not_parse_duplicate_or_malformed_key_value_pairs() {
+    use heapless::String;
+    use heapless::index_map::FnvIndexMap;
+
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Long("set"), "set", AtMost::Many)]);
+
+    let argv = &["--set", "a=1", "--set", "a=2"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    let map = args.try_get_map::<FnvIndexMap<String<8>, u32, 4>>("set", '=');
+    assert_that!(map).is_err().is_equal_to(Error::DuplicateKey);
+
+    for argv in [["--set", "a"], ["--set", "=1"]] {
+        let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+        let map = args.try_get_map::<FnvIndexMap<String<8>, u32, 4>>("set", '=');
+        assert_that!(map).is_err().is_equal_to(Error::MalformedPair);
+    }
+
+    let argv = &["--set", "a=x"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    let map = args.try_get_map::<FnvIndexMap<String<8>, u32, 4>>("set", '=');
+    assert_that!(map)
+        .is_err()
+        .is_equal_to(Error::InvalidArgument);
+}
+
+#[test]
+fn it_should_split_delimited_values_of_repeated_flag() {
+    static LOOKUP: ArgLookupTable<'_> =
+        ArgLookupTable::new(&[(Flag::Long("channels"), "channels", AtMost::Many)])
+            .with_options(&[("channels", ArgOptions::new().with_delimiter(','))]);
+
+    let argv = &["--channels", "1,2", "file", "--channels=5"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.try_get_many::<Vec<_, PARSED_VALUES_CAPACITY>, u32>("channels"))
+        .is_ok()
+        .is_some()
+        .is_equal_to(Vec::from_slice(&[1, 2, 5]).unwrap());
+    assert_that!(args.try_get_words::<Vec<_, PARSED_VALUES_CAPACITY>>("channels"))
+        .is_ok()
+        .is_some()
+        .is_equal_to(Vec::from_slice(&["1", "2", "5"]).unwrap());
+    assert_that!(args.values_of("channels").map(|x| x.iter().count()))
+        .is_some()
+        .is_equal_to(2);
+    assert_that!(args.positionals().next())
+        .is_some()
+        .is_equal_to("file");
+}
+
+#[test]
+fn it_should_take_missing_values_from_provider() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Long("baud"), "baud", AtMost::One),
+        (Flag::Long("channels"), "channels", AtMost::Many),
+        (Flag::Long("name"), "name", AtMost::One),
+        (Flag::Long("mode"), "mode", AtMost::One),
+    ])
+    .with_options(&[
+        ("baud", ArgOptions::new().with_setting("uart.baud")),
+        (
+            "channels",
+            ArgOptions::new()
+                .with_setting("adc.channels")
+                .with_delimiter(','),
+        ),
+        ("name", ArgOptions::new().with_setting("device.name")),
+    ]);
+
+    let settings = [
+        ("uart.baud", "9600"),
+        ("adc.channels", "1,2"),
+        ("device.name", "flash"),
+    ];
+
+    let argv = &["--name", "cmdline"];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> =
+        ParsedArgs::try_parse_with(argv, &LOOKUP, &settings).unwrap();
+
+    assert_that!(args.try_get_one::<u32>("baud"))
+        .is_ok()
+        .is_equal_to(Some(Some(9600)));
+    assert_that!(args.try_get_many::<Vec<_, PARSED_VALUES_CAPACITY>, u32>("channels"))
+        .is_ok()
+        .is_some()
+        .is_equal_to(Vec::from_slice(&[1, 2]).unwrap());
+    assert_that!(args.try_get_words::<Vec<_, PARSED_VALUES_CAPACITY>>("name"))
+        .is_ok()
+        .is_some()
+        .is_equal_to(Vec::from_slice(&["cmdline"]).unwrap());
+
+    assert_that!(args.source_of("baud")).is_equal_to(ValueSource::Provider);
+    assert_that!(args.source_of("name")).is_equal_to(ValueSource::CommandLine);
+    assert_that!(args.source_of("mode")).is_equal_to(ValueSource::Default);
+}
+
+#[test]
+fn it_should_not_copy_arg_options_in_parsed_args() {
+    // A named argument only holds its identifier and the range of its values, while its options
+    // are looked up in the table.
+    assert_that!(core::mem::size_of::<Arg<'_>>())
+        .is_less_than_or_equal_to(5 * core::mem::size_of::<usize>());
+    assert_that!(core::mem::size_of::<ParsedArgs<'_, PARSED_ARG_CAPACITY>>())
+        .is_less_than_or_equal_to(PARSED_ARG_CAPACITY * 7 * core::mem::size_of::<usize>() + 64);
+}
+
+#[test]
+fn it_should_report_deprecated_args() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static REPORTED: AtomicUsize = AtomicUsize::new(0);
+
+    fn hook(id: &str, note: &str) {
+        assert_eq!(("old", "use --new"), (id, note));
+        REPORTED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Long("old"), "old", AtMost::One),
+        (Flag::Long("new"), "new", AtMost::One),
+    ])
+    .with_options(&[("old", ArgOptions::new().with_deprecated("use --new"))])
+    .with_deprecation_hook(hook);
+
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> =
+        ParsedArgs::parse_from(&["--old", "1", "--old", "2"], &LOOKUP);
+
+    assert_that!(args.try_get_one::<u32>("old"))
+        .is_ok()
+        .is_some()
+        .is_some()
+        .is_equal_to(1);
+
+    let _: ParsedArgs<'_, PARSED_ARG_CAPACITY> = ParsedArgs::parse_from(&["--new", "1"], &LOOKUP);
+
+    assert_eq!(1, REPORTED.load(Ordering::Relaxed));
+}
+
+#[test]
+fn it_should_list_all_the_flags_of_an_arg() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Short('b'), "baud", AtMost::One),
+        (Flag::Long("parity"), "parity", AtMost::One),
+        (Flag::Long("baud"), "baud", AtMost::One),
+    ]);
+
+    assert_that!(LOOKUP.flags_of("baud").collect::<Vec<_, 4>>().as_slice())
+        .is_equal_to([Flag::Short('b'), Flag::Long("baud")].as_slice());
+    assert_that!(LOOKUP.flags_of("stop").next()).is_none();
+}
+
+#[test]
+fn it_should_look_up_flags_in_sorted_table() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Short('b'), "baud", AtMost::One),
+        (Flag::Short('v'), "verbose", AtMost::One),
+        (Flag::Long("baud"), "baud", AtMost::One),
+        (Flag::Long("parity"), "parity", AtMost::One),
+        (Flag::Long("verbose"), "verbose", AtMost::One),
+        (Flag::Long("version"), "version", AtMost::One),
+    ])
+    .with_matching(FlagMatching::Prefix)
+    .with_options(&[
+        ("baud", ArgOptions::new().with_setting("uart.baud")),
+        ("parity", ArgOptions::new().with_require_equals(true)),
+    ])
+    .with_sorted();
+
+    assert_that!(LOOKUP.metadata_of(&Flag::Short('v'))).is_equal_to(Some(("verbose", AtMost::One)));
+    assert_that!(LOOKUP.metadata_of(&Flag::Long("par"))).is_equal_to(Some(("parity", AtMost::One)));
+    assert_that!(LOOKUP.metadata_of(&Flag::Short('x'))).is_none();
+    assert_that!(LOOKUP.try_metadata_of(&Flag::Long("ver"))).is_err();
+    assert_that!(LOOKUP.options_of("parity").require_equals()).is_true();
+    assert_that!(LOOKUP.options_of("verbose")).is_equal_to(ArgOptions::new());
+
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY> =
+        ParsedArgs::parse_from(&["--verb", "1", "-b", "9600", "file", "-v", "2"], &LOOKUP);
+
+    assert_that!(args.try_get_many::<Vec<_, PARSED_VALUES_CAPACITY>, u32>("verbose"))
+        .is_ok()
+        .is_some()
+        .is_equal_to(Vec::from_slice(&[1, 2]).unwrap());
+    assert_that!(args.try_get_one::<u32>("baud"))
+        .is_ok()
+        .is_equal_to(Some(Some(9600)));
+    assert_that!(args.positionals().collect::<Vec<_, 2>>().as_slice())
+        .is_equal_to(["file"].as_slice());
+}
+
+#[test]
+#[should_panic(expected = "not sorted by flag")]
+fn it_should_reject_unsorted_table() {
+    let _ = ArgLookupTable::new(&[
+        (Flag::Long("baud"), "baud", AtMost::One),
+        (Flag::Short('b'), "baud", AtMost::One),
+    ])
+    .with_sorted();
+}
+
+#[test]
+fn it_should_parse_byte_words() {
+    static LOOKUP: ArgLookupTable<'_> = ArgLookupTable::new(&[
+        (Flag::Long("count"), "count", AtMost::One),
+        (Flag::Long("data"), "data", AtMost::Many),
+        (Flag::Long("key"), "key", AtMost::One),
+    ])
+    .with_options(&[("data", ArgOptions::new().with_delimiter(','))]);
+
+    let argv: [&[u8]; 6] = [
+        b"--count",
+        b"2",
+        b"--data",
+        b"\x00\xff,\x01",
+        b"--key=\xfe",
+        b"\xff",
+    ];
+    let args: ParsedArgs<'_, PARSED_ARG_CAPACITY, Bytes<'_>> =
+        ParsedArgs::parse_from(argv, &LOOKUP);
+
+    assert_that!(args.try_get_one::<u32>("count"))
+        .is_ok()
+        .is_equal_to(Some(Some(2)));
+    assert_that!(args.try_get_bytes::<Vec<_, PARSED_VALUES_CAPACITY>>("data"))
+        .is_ok()
+        .is_some()
+        .is_equal_to(Vec::from_slice(&[b"\x00\xff".as_slice(), b"\x01".as_slice()]).unwrap());
+    assert_that!(args.bytes_of("key").and_then(|mut x| x.next()))
+        .is_equal_to(Some(b"\xfe".as_slice()));
+    assert_that!(args.positional_bytes().next()).is_equal_to(Some(b"\xff".as_slice()));
+
+    // Typed values must be valid UTF-8 strings.
+    assert_that!(args.try_get_one::<heapless::String<4>>("key")).is_err();
+    assert_that!(args.try_get_positional::<u8>(0)).is_err();
+}
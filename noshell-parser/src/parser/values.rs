@@ -1,26 +1,133 @@
 //! Values.
 
+use crate::parser::Word;
+
 /// Iterator over argument values.
 #[derive(Clone, Debug, PartialEq)]
-pub struct Values<'a> {
-    slice: &'a [&'a str],
+pub struct Values<'a, W = &'a str> {
+    slice: &'a [W],
+    offset: usize,
+    default: Option<&'a str>,
+    delimiter: Option<char>,
 }
 
-impl<'a> Values<'a> {
+impl<'a, W> Values<'a, W> {
     /// Create a new value iterator.
-    pub fn new(slice: &'a [&'a str]) -> Self {
-        Values { slice }
-    }
-
-    /// Get an iterator.
-    pub fn iter(&self) -> impl Iterator<Item = &'a str> {
-        self.slice.iter().copied()
+    pub fn new(slice: &'a [W]) -> Self {
+        Values {
+            slice,
+            offset: 0,
+            default: None,
+            delimiter: None,
+        }
     }
 
     /// Create an empty value iterator.
     pub fn empty() -> Self {
         Self::new(&[])
     }
+
+    /// Skip the given number of bytes in the first value (e.g. the `--color=` of an attached
+    /// value).
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Add a default value, which is not part of the parsed words.
+    pub fn with_default(mut self, default: Option<&'a str>) -> Self {
+        self.default = default;
+        self
+    }
+
+    /// Split every value on the given delimiter, if any, without copy.
+    pub fn with_delimiter(mut self, delimiter: Option<char>) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+}
+
+impl<'a, W> Values<'a, W>
+where
+    W: AsRef<str>,
+{
+    /// Get an iterator.
+    pub fn iter(&self) -> impl Iterator<Item = &'a str> + use<'a, W> {
+        let offset = self.offset;
+        let delimiter = self.delimiter;
+
+        self.slice
+            .iter()
+            .map(AsRef::as_ref)
+            .enumerate()
+            .map(move |(i, x)| if i == 0 { &x[offset..] } else { x })
+            .chain(self.default)
+            .flat_map(move |x| x.split(move |c| Some(c) == delimiter))
+    }
+}
+
+impl<'a, W> Values<'a, W>
+where
+    W: Word,
+{
+    /// Get an iterator over the values as bytes, which are not necessarily valid UTF-8 strings.
+    pub fn bytes(&self) -> impl Iterator<Item = &'a [u8]> + use<'a, W> {
+        let offset = self.offset;
+        let delimiter = self.delimiter;
+
+        self.slice
+            .iter()
+            .map(Word::as_bytes)
+            .enumerate()
+            .map(move |(i, x)| if i == 0 { &x[offset..] } else { x })
+            .chain(self.default.map(str::as_bytes))
+            .flat_map(move |x| SplitBytes::new(x, delimiter))
+    }
+}
+
+/// Iterator over the parts of bytes separated by a delimiter, without copy.
+struct SplitBytes<'a> {
+    rest: Option<&'a [u8]>,
+    delimiter: [u8; 4],
+    len: usize,
+}
+
+impl<'a> SplitBytes<'a> {
+    fn new(input: &'a [u8], delimiter: Option<char>) -> Self {
+        let mut buf = [0u8; 4];
+        let len = delimiter.map_or(0, |x| x.encode_utf8(&mut buf).len());
+
+        SplitBytes {
+            rest: Some(input),
+            delimiter: buf,
+            len,
+        }
+    }
+}
+
+impl<'a> Iterator for SplitBytes<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
+        let delimiter = &self.delimiter[..self.len];
+
+        // Without delimiter, the input is a single part.
+        let found = (!delimiter.is_empty())
+            .then(|| rest.windows(delimiter.len()).position(|x| x == delimiter))
+            .flatten();
+
+        match found {
+            Some(i) => {
+                self.rest = Some(&rest[i + delimiter.len()..]);
+                Some(&rest[..i])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
 }
 
 /// The number of expected values on a given argument.
@@ -34,4 +141,7 @@ pub enum AtMost {
 
     /// One or more values expected.
     Many,
+
+    /// All the remaining words are expected as values, verbatim, even if they look like flags.
+    Trailing,
 }
@@ -0,0 +1,113 @@
+//! Words.
+
+use heapless::{String, Vec};
+
+use crate::parser::{Error, Result};
+
+/// Word stored by the parser, which is either a string or raw bytes (see [`Bytes`]).
+pub trait Word {
+    /// Get the bytes of the word.
+    fn as_bytes(&self) -> &[u8];
+}
+
+impl<T> Word for T
+where
+    T: AsRef<str> + ?Sized,
+{
+    #[inline(always)]
+    fn as_bytes(&self) -> &[u8] {
+        self.as_ref().as_bytes()
+    }
+}
+
+/// Word made of raw bytes, which are not necessarily a valid UTF-8 string (e.g. a binary payload
+/// read from a serial line).
+///
+/// The flags are still recognised, as long as they are valid UTF-8, and the values are available
+/// as bytes (see [`ParsedArgs::try_get_bytes`](crate::parser::ParsedArgs::try_get_bytes)). Typed
+/// values are only parsed from valid UTF-8 strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Bytes<'a>(pub &'a [u8]);
+
+impl Word for Bytes<'_> {
+    #[inline(always)]
+    fn as_bytes(&self) -> &[u8] {
+        self.0
+    }
+}
+
+/// Conversion of an item of the input iterator into a word stored by the parser.
+///
+/// This allows parsing from borrowed strings, owned strings, or the fallible output of a command
+/// line lexer.
+pub trait IntoWord<W> {
+    /// Convert the item into a word.
+    fn into_word(self) -> Result<W>;
+}
+
+impl<'a> IntoWord<&'a str> for &'a str {
+    #[inline(always)]
+    fn into_word(self) -> Result<&'a str> {
+        Ok(self)
+    }
+}
+
+impl<'a> IntoWord<&'a str> for &'_ &'a str {
+    #[inline(always)]
+    fn into_word(self) -> Result<&'a str> {
+        Ok(*self)
+    }
+}
+
+impl<'a, const SIZE: usize> IntoWord<&'a str> for &'a String<SIZE> {
+    #[inline(always)]
+    fn into_word(self) -> Result<&'a str> {
+        Ok(self.as_str())
+    }
+}
+
+impl<const SIZE: usize> IntoWord<String<SIZE>> for String<SIZE> {
+    #[inline(always)]
+    fn into_word(self) -> Result<String<SIZE>> {
+        Ok(self)
+    }
+}
+
+impl<W, T, E> IntoWord<W> for core::result::Result<T, E>
+where
+    T: IntoWord<W>,
+{
+    #[inline(always)]
+    fn into_word(self) -> Result<W> {
+        self.map_err(|_| Error::InvalidInput)?.into_word()
+    }
+}
+
+impl<'a> IntoWord<Bytes<'a>> for &'a [u8] {
+    #[inline(always)]
+    fn into_word(self) -> Result<Bytes<'a>> {
+        Ok(Bytes(self))
+    }
+}
+
+impl<'a> IntoWord<Bytes<'a>> for &'_ &'a [u8] {
+    #[inline(always)]
+    fn into_word(self) -> Result<Bytes<'a>> {
+        Ok(Bytes(self))
+    }
+}
+
+impl<'a, const SIZE: usize> IntoWord<Bytes<'a>> for &'a Vec<u8, SIZE> {
+    #[inline(always)]
+    fn into_word(self) -> Result<Bytes<'a>> {
+        Ok(Bytes(self.as_slice()))
+    }
+}
+
+impl<'a> IntoWord<Bytes<'a>> for Bytes<'a> {
+    #[inline(always)]
+    fn into_word(self) -> Result<Bytes<'a>> {
+        Ok(self)
+    }
+}
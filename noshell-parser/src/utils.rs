@@ -1,8 +1,12 @@
 //! Parser utilities.
 
+use core::cmp::Ordering;
+use core::fmt;
+
 use heapless::Vec;
 
 use crate::Error;
+use crate::lexer::Flag;
 
 /// Check if the argument `Option` is `None` and return an error `Error::InvalidArgument` if true
 #[inline(always)]
@@ -25,3 +29,264 @@ pub fn check_vec_is_missing<T, const SIZE: usize>(v: Vec<T, SIZE>) -> Result<Vec
 
     Ok(v)
 }
+
+/// Check if the `Vec` is empty and return `None` if true.
+#[inline(always)]
+pub fn non_empty_vec<T, const SIZE: usize>(v: Vec<T, SIZE>) -> Option<Vec<T, SIZE>> {
+    (!v.is_empty()).then_some(v)
+}
+
+/// Maximum number of characters compared by [`edit_distance`].
+pub const EDIT_DISTANCE_MAX_LEN: usize = 32;
+
+/// Compute the edit distance (i.e. Levenshtein distance) between two strings, without allocation.
+///
+/// Return `None` if one of the strings has more than [`EDIT_DISTANCE_MAX_LEN`] characters.
+pub fn edit_distance(a: &str, b: &str) -> Option<usize> {
+    let len = b.chars().count();
+
+    if len > EDIT_DISTANCE_MAX_LEN || a.chars().count() > EDIT_DISTANCE_MAX_LEN {
+        return None;
+    }
+
+    // Single row of the distance matrix, which is updated in place for every character of `a`.
+    let mut row = [0usize; EDIT_DISTANCE_MAX_LEN + 1];
+    for (j, x) in row.iter_mut().enumerate().take(len + 1) {
+        *x = j;
+    }
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.chars().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    Some(row[len])
+}
+
+/// Find the closest candidate to the input, if any is close enough to be a likely typo.
+///
+/// This could be used for suggesting flags, subcommand names or value names.
+pub fn closest<'a, I>(input: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    // Allow roughly one typo every three characters.
+    let threshold = (input.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .filter_map(|x| Some((edit_distance(input, x)?, x)))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, x)| x)
+}
+
+/// Maximum number of candidates kept on an ambiguous prefix.
+pub const MAX_CANDIDATES: usize = 4;
+
+/// Candidates matching an ambiguous prefix. Only the first [`MAX_CANDIDATES`] are kept, so that
+/// no allocation is required.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Candidates<T> {
+    items: [Option<T>; MAX_CANDIDATES],
+}
+
+impl<T: Copy> Candidates<T> {
+    /// Create an empty set of candidates.
+    pub const fn new() -> Self {
+        Candidates {
+            items: [None; MAX_CANDIDATES],
+        }
+    }
+
+    /// Add a candidate, which is dropped if there is no space left.
+    pub fn push(&mut self, item: T) {
+        if let Some(slot) = self.items.iter_mut().find(|x| x.is_none()) {
+            *slot = Some(item);
+        }
+    }
+
+    /// Get an iterator over the candidates.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.items.iter().map_while(|x| *x)
+    }
+}
+
+impl<T: Copy> Default for Candidates<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + fmt::Display> fmt::Display for Candidates<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, item) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "`{}`", item)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Find the candidate that is either equal to the input, or the only one starting with it.
+///
+/// This could be used for accepting abbreviations of flags or command names. On an ambiguous
+/// prefix, return the matching candidates.
+pub fn find_by_prefix<'a, I>(
+    input: &str,
+    candidates: I,
+) -> Result<Option<&'a str>, Candidates<&'a str>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut found = None;
+    let mut matches = Candidates::new();
+    let mut ambiguous = false;
+
+    for candidate in candidates {
+        if candidate == input {
+            return Ok(Some(candidate));
+        }
+
+        if input.is_empty() || !candidate.starts_with(input) {
+            continue;
+        }
+
+        matches.push(candidate);
+        ambiguous |= found.is_some();
+        found = found.or(Some(candidate));
+    }
+
+    if ambiguous {
+        return Err(matches);
+    }
+
+    Ok(found)
+}
+
+/// Compare two strings byte by byte, as [`str::cmp`] does, in a constant context.
+pub const fn cmp_str(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut i = 0;
+
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return if a[i] < b[i] {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+        }
+
+        i += 1;
+    }
+
+    cmp_usize(a.len(), b.len())
+}
+
+/// Compare two integers in a constant context.
+const fn cmp_usize(a: usize, b: usize) -> Ordering {
+    if a < b {
+        Ordering::Less
+    } else if a > b {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+/// Compare two flags in a constant context. Short flags come first, ordered by character, then
+/// long flags, ordered by name.
+pub const fn cmp_flags(a: &Flag<'_>, b: &Flag<'_>) -> Ordering {
+    match (a, b) {
+        (Flag::Short(a), Flag::Short(b)) => cmp_usize(*a as usize, *b as usize),
+        (Flag::Short(_), Flag::Long(_)) => Ordering::Less,
+        (Flag::Long(_), Flag::Short(_)) => Ordering::Greater,
+        (Flag::Long(a), Flag::Long(b)) => cmp_str(a, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use speculoos::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn it_should_compare_flags() {
+        use core::cmp::Ordering;
+
+        assert_that!(cmp_flags(&Flag::Short('a'), &Flag::Short('b'))).is_equal_to(Ordering::Less);
+        assert_that!(cmp_flags(&Flag::Short('z'), &Flag::Long("a"))).is_equal_to(Ordering::Less);
+        assert_that!(cmp_flags(&Flag::Long("verbose"), &Flag::Long("verb")))
+            .is_equal_to(Ordering::Greater);
+        assert_that!(cmp_flags(&Flag::Long("baud"), &Flag::Long("baud")))
+            .is_equal_to(Ordering::Equal);
+        assert_that!(cmp_str("a-b", "a_b")).is_equal_to("a-b".cmp("a_b"));
+    }
+
+    #[test]
+    fn it_should_compute_edit_distance() {
+        assert_that!(edit_distance("", "")).is_some().is_equal_to(0);
+        assert_that!(edit_distance("retries", "retries"))
+            .is_some()
+            .is_equal_to(0);
+        assert_that!(edit_distance("retires", "retries"))
+            .is_some()
+            .is_equal_to(2);
+        assert_that!(edit_distance("verbose", "verb"))
+            .is_some()
+            .is_equal_to(3);
+        assert_that!(edit_distance("kitten", "sitting"))
+            .is_some()
+            .is_equal_to(3);
+    }
+
+    #[test]
+    fn it_should_find_closest_candidate() {
+        let candidates = ["retries", "verbose", "timeout"];
+
+        assert_that!(closest("retires", candidates))
+            .is_some()
+            .is_equal_to("retries");
+        assert_that!(closest("verbos", candidates))
+            .is_some()
+            .is_equal_to("verbose");
+        assert_that!(closest("color", candidates)).is_none();
+    }
+
+    #[test]
+    fn it_should_find_by_prefix() {
+        let candidates = ["status", "start", "stop", "reboot"];
+
+        assert_that!(find_by_prefix("reb", candidates))
+            .is_ok()
+            .is_some()
+            .is_equal_to("reboot");
+        assert_that!(find_by_prefix("stat", candidates))
+            .is_ok()
+            .is_some()
+            .is_equal_to("status");
+        assert_that!(find_by_prefix("stop", candidates))
+            .is_ok()
+            .is_some()
+            .is_equal_to("stop");
+        assert_that!(find_by_prefix("help", candidates))
+            .is_ok()
+            .is_none();
+        assert_that!(find_by_prefix("st", candidates))
+            .is_err()
+            .matches(|x| x.iter().eq(["status", "start", "stop"]));
+    }
+}
@@ -0,0 +1,397 @@
+//! A writer of command lines, which is the inverse of the parser.
+
+use core::fmt::{self, Display, Write};
+
+use crate::lexer::{Flag, Token};
+use crate::parser::AtMost;
+
+/// Writer of arguments as a command line, which can be split back into the same words by a POSIX
+/// command line lexer (e.g. `noshell::cmdline::lexer::split`).
+///
+/// Words are quoted if empty, if they contain whitespaces or if they start with a quote. Values
+/// are written so that they are never taken as flags, otherwise an error is returned.
+///
+/// Positional arguments which could be taken as flags or as the values of a previous flag are
+/// written after the end of options (i.e. `--`), so that no flag can be written after them.
+pub struct ArgsWriter<'a, O: ?Sized> {
+    out: &'a mut O,
+    empty: bool,
+    pending: bool,
+    options_done: bool,
+}
+
+impl<'a, O> ArgsWriter<'a, O>
+where
+    O: Write + ?Sized,
+{
+    /// Create a new writer.
+    pub fn new(out: &'a mut O) -> Self {
+        ArgsWriter {
+            out,
+            empty: true,
+            pending: false,
+            options_done: false,
+        }
+    }
+
+    /// Write a flag without any value. The flag waits for values until another flag is written.
+    pub fn flag(&mut self, flag: Flag<'_>) -> fmt::Result {
+        self.check_options()?;
+        self.word(flag)?;
+        self.pending = true;
+        Ok(())
+    }
+
+    /// Write a flag and its value. The value is attached to long flags (e.g. `--name=value`), so
+    /// that it cannot be taken as a flag. It fails if the value of a short flag looks like a
+    /// flag.
+    pub fn flag_with_value<T>(&mut self, flag: Flag<'_>, value: T) -> fmt::Result
+    where
+        T: Display,
+    {
+        self.check_options()?;
+
+        match flag {
+            Flag::Long(name) => self.word(format_args!("--{}={}", name, value))?,
+
+            Flag::Short(_) => {
+                if Scan::of(&value)?.is_flag() {
+                    return Err(fmt::Error);
+                }
+
+                self.word(flag)?;
+                self.word(value)?;
+            }
+        }
+
+        self.pending = false;
+        Ok(())
+    }
+
+    /// Write a flag and its values, given the number of values that the flag takes when parsed.
+    ///
+    /// The values of a long flag are attached to their own flag (e.g. `--name=a --name=b`), as
+    /// with [`ArgsWriter::flag_with_value`]. The values of a short flag which takes many values
+    /// follow a single flag (e.g. `-n a b`), which then waits for more values until another flag
+    /// or the end of options is written. It fails if a flag without value is given values.
+    pub fn flag_with_values<I>(
+        &mut self,
+        flag: Flag<'_>,
+        expected: AtMost,
+        values: I,
+    ) -> fmt::Result
+    where
+        I: IntoIterator,
+        I::Item: Display,
+    {
+        let mut values = values.into_iter().peekable();
+
+        match (flag, expected) {
+            (_, AtMost::Zero) if values.peek().is_some() => Err(fmt::Error),
+            (_, AtMost::Zero) => Ok(()),
+
+            (Flag::Short(_), AtMost::Many | AtMost::Trailing) => {
+                if values.peek().is_none() {
+                    return Ok(());
+                }
+
+                self.check_options()?;
+                self.word(flag)?;
+
+                for value in values {
+                    if Scan::of(&value)?.is_flag() {
+                        return Err(fmt::Error);
+                    }
+
+                    self.word(value)?;
+                }
+
+                self.pending = true;
+                Ok(())
+            }
+
+            _ => values.try_for_each(|value| self.flag_with_value(flag, value)),
+        }
+    }
+
+    /// Write words verbatim (e.g. raw arguments). Any of them may be a flag waiting for values.
+    pub fn raw<I>(&mut self, words: I) -> fmt::Result
+    where
+        I: IntoIterator,
+        I::Item: Display,
+    {
+        for word in words {
+            self.check_options()?;
+            self.word(word)?;
+            self.pending = true;
+        }
+
+        Ok(())
+    }
+
+    /// Write a positional argument. If it could be taken as a flag or as the value of a previous
+    /// flag, then the end of options is written first, which closes the previous flag.
+    pub fn positional<T>(&mut self, value: T) -> fmt::Result
+    where
+        T: Display,
+    {
+        if !self.options_done && (self.pending || Scan::of(&value)?.is_flag()) {
+            self.word("--")?;
+            self.options_done = true;
+            self.pending = false;
+        }
+
+        self.word(value)
+    }
+
+    /// Write the trailing words, after their flag if any. Without flag, the first word is a
+    /// positional argument (see [`ArgsWriter::positional`]).
+    pub fn trailing<I>(&mut self, flag: Option<Flag<'_>>, words: I) -> fmt::Result
+    where
+        I: IntoIterator,
+        I::Item: Display,
+    {
+        let mut words = words.into_iter();
+
+        match flag {
+            Some(flag) => self.flag(flag)?,
+
+            None => {
+                let Some(first) = words.next() else {
+                    return Ok(());
+                };
+
+                self.positional(first)?;
+            }
+        }
+
+        for word in words {
+            self.word(word)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that flags can still be written, before the end of options.
+    fn check_options(&self) -> fmt::Result {
+        if self.options_done {
+            return Err(fmt::Error);
+        }
+
+        Ok(())
+    }
+
+    /// Write a single word, quoted if required.
+    fn word<T>(&mut self, word: T) -> fmt::Result
+    where
+        T: Display,
+    {
+        let scan = Scan::of(&word)?;
+
+        let quote = if !scan.needs_quotes() {
+            None
+        } else if !scan.single_quote {
+            Some('\'')
+        } else if !scan.double_quote {
+            Some('"')
+        } else {
+            // There is no escape sequence in quoted words.
+            return Err(fmt::Error);
+        };
+
+        if !self.empty {
+            self.out.write_char(' ')?;
+        }
+
+        self.empty = false;
+
+        match quote {
+            Some(quote) => write!(self.out, "{}{}{}", quote, word, quote),
+            None => write!(self.out, "{}", word),
+        }
+    }
+}
+
+/// Key-value pair written with its separator (e.g. `key=value`), as parsed into a map.
+pub struct Pair<K, V>(pub K, pub char, pub V);
+
+impl<K: Display, V: Display> Display for Pair<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.0, self.1, self.2)
+    }
+}
+
+/// Properties of a formatted word, which are collected without allocation.
+#[derive(Default)]
+struct Scan {
+    head: [u8; 3],
+    len: usize,
+    whitespace: bool,
+    single_quote: bool,
+    double_quote: bool,
+}
+
+impl Scan {
+    fn of<T: Display>(word: &T) -> Result<Self, fmt::Error> {
+        let mut scan = Scan::default();
+        write!(scan, "{}", word)?;
+        Ok(scan)
+    }
+
+    fn needs_quotes(&self) -> bool {
+        self.len == 0 || self.whitespace || matches!(self.head[0], b'\'' | b'"')
+    }
+
+    fn is_flag(&self) -> bool {
+        // The end of options is parsed as such, so that it cannot be a value either.
+        if self.len == 2 && self.head.starts_with(b"--") {
+            return true;
+        }
+
+        // Only long flags can be longer than the head.
+        if self.len > self.head.len() {
+            return self.head.starts_with(b"--");
+        }
+
+        core::str::from_utf8(&self.head[..self.len]).is_ok_and(Token::is_flag)
+    }
+}
+
+impl Write for Scan {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, &x) in s.as_bytes().iter().enumerate() {
+            if let Some(slot) = self.head.get_mut(self.len + i) {
+                *slot = x;
+            }
+        }
+
+        self.len += s.len();
+        self.whitespace |= s.contains([' ', '\t', '\n']);
+        self.single_quote |= s.contains('\'');
+        self.double_quote |= s.contains('"');
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::String;
+    use speculoos::prelude::*;
+
+    use super::*;
+
+    fn write_with<F>(f: F) -> Result<String<64>, fmt::Error>
+    where
+        F: FnOnce(&mut ArgsWriter<'_, String<64>>) -> fmt::Result,
+    {
+        let mut out = String::new();
+        f(&mut ArgsWriter::new(&mut out))?;
+        Ok(out)
+    }
+
+    #[test]
+    fn it_should_write_flags_and_values() {
+        let out = write_with(|w| {
+            w.flag(Flag::Long("verbose"))?;
+            w.flag_with_value(Flag::Long("count"), -2)?;
+            w.flag_with_value(Flag::Short('n'), "5")
+        });
+
+        assert_that!(out)
+            .is_ok()
+            .is_equal_to(String::try_from("--verbose --count=-2 -n 5").unwrap());
+    }
+
+    #[test]
+    fn it_should_quote_words() {
+        let out = write_with(|w| {
+            w.flag_with_value(Flag::Long("name"), "a b")?;
+            w.flag_with_value(Flag::Short('n'), "")?;
+            w.trailing(None, ["it's", "'x'", "ok\""])
+        });
+
+        assert_that!(out)
+            .is_ok()
+            .is_equal_to(String::try_from("'--name=a b' -n '' it's \"'x'\" ok\"").unwrap());
+
+        let out = write_with(|w| w.raw(["'\" "]));
+        assert_that!(out).is_err();
+    }
+
+    #[test]
+    fn it_should_write_many_values_of_short_flags_after_a_single_flag() {
+        let out = write_with(|w| {
+            w.flag_with_values(Flag::Short('c'), AtMost::Many, [1, 2])?;
+            w.flag_with_values(Flag::Long("port"), AtMost::Many, [3, 4])?;
+            w.flag_with_values(Flag::Short('n'), AtMost::One, [5, 6])
+        });
+
+        assert_that!(out)
+            .is_ok()
+            .is_equal_to(String::try_from("-c 1 2 --port=3 --port=4 -n 5 -n 6").unwrap());
+
+        // The values of the short flag would take the positional arguments.
+        let out = write_with(|w| {
+            w.flag_with_values(Flag::Short('c'), AtMost::Many, [1, 2])?;
+            w.trailing(None, ["cmd", "arg"])
+        });
+        assert_that!(out)
+            .is_ok()
+            .is_equal_to(String::try_from("-c 1 2 -- cmd arg").unwrap());
+
+        let out = write_with(|w| w.flag_with_values(Flag::Short('c'), AtMost::Many, ["-x"]));
+        assert_that!(out).is_err();
+    }
+
+    #[test]
+    fn it_should_not_write_values_taken_as_flags() {
+        assert_that!(write_with(|w| w.flag_with_value(Flag::Short('n'), "-x"))).is_err();
+        assert_that!(write_with(|w| w.flag_with_value(Flag::Short('n'), "--long"))).is_err();
+        assert_that!(write_with(|w| w.flag_with_value(Flag::Short('n'), -1))).is_ok();
+        assert_that!(write_with(|w| w.flag_with_value(Flag::Short('n'), "--"))).is_err();
+        assert_that!(write_with(
+            |w| w.trailing(Some(Flag::Long("exec")), ["--x", "y"])
+        ))
+        .is_ok()
+        .is_equal_to(String::try_from("--exec --x y").unwrap());
+    }
+
+    #[test]
+    fn it_should_write_positional_arguments_after_end_of_options() {
+        let out = write_with(|w| {
+            w.flag(Flag::Long("verbose"))?;
+            w.positional("x")?;
+            w.positional("-y")
+        });
+        assert_that!(out)
+            .is_ok()
+            .is_equal_to(String::try_from("--verbose -- x -y").unwrap());
+
+        let out = write_with(|w| {
+            w.positional(-5)?;
+            w.trailing(None, ["--x", "y"])
+        });
+        assert_that!(out)
+            .is_ok()
+            .is_equal_to(String::try_from("-5 -- --x y").unwrap());
+
+        let out = write_with(|w| w.positional("--"));
+        assert_that!(out)
+            .is_ok()
+            .is_equal_to(String::try_from("-- --").unwrap());
+
+        // Flags cannot follow the end of options.
+        let out = write_with(|w| {
+            w.positional("-y")?;
+            w.flag_with_value(Flag::Long("count"), 1)
+        });
+        assert_that!(out).is_err();
+
+        let out = write_with(|w| {
+            w.positional("-y")?;
+            w.trailing(Some(Flag::Long("exec")), ["x"])
+        });
+        assert_that!(out).is_err();
+    }
+}
@@ -0,0 +1,28 @@
+use noshell_macros::Parser;
+use speculoos::prelude::*;
+
+#[test]
+fn it_should_derive_against_parser_crate_alone() {
+    #[derive(Debug, Parser)]
+    #[noshell(parser_crate = "noshell_parser")]
+    struct MyArgs {
+        #[arg(short, long)]
+        verbose: Option<Option<bool>>,
+
+        count: u32,
+    }
+
+    let argv = &["-v", "--count", "3"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.verbose == Some(None) && x.count == 3);
+
+    let argv = &["--count", "x"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_err()
+        .is_equal_to(noshell_parser::Error::InvalidArgument);
+}
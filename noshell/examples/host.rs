@@ -0,0 +1,16 @@
+//! Parse the process arguments with the same command struct as the one running on the device.
+//!
+//! Run with `cargo run -p noshell --features std --example host -- --retries 3`.
+
+#[derive(Debug, noshell::Parser)]
+struct MyArgs {
+    retries: Option<u32>,
+}
+
+fn main() {
+    let args = MyArgs::parse();
+
+    if let Some(retries) = args.retries {
+        println!("You have {} retries left!", retries);
+    }
+}
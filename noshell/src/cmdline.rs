@@ -14,6 +14,9 @@ use noterm::{Executable, Queuable};
 pub mod lexer;
 pub mod prompt;
 
+#[cfg(feature = "parser")]
+pub mod wizard;
+
 pub use prompt::Prompt;
 
 #[cfg(test)]
@@ -37,6 +40,11 @@ pub enum Error {
     #[error("no space left")]
     NoSpaceLeft,
 
+    /// An error comes from the parsing of arguments.
+    #[cfg(feature = "parser")]
+    #[error(transparent)]
+    Parser(#[from] crate::parser::Error),
+
     /// Unknown error.
     #[error("unknown error")]
     Unknown,
@@ -51,6 +59,36 @@ pub async fn readline<OutputTy, EventsTy, ContentTy, const SIZE: usize>(
     events: EventsTy,
     output: &mut OutputTy,
 ) -> Result<String<SIZE>>
+where
+    OutputTy: io::blocking::Write,
+    EventsTy: Stream<Item = io::Result<Event>>,
+    ContentTy: Iterator + Clone,
+    <ContentTy as Iterator>::Item: fmt::Display,
+{
+    read_line_with(prompt, events, output, false).await
+}
+
+/// Read a line without echoing it, each character being printed as `*` (e.g. a password).
+pub async fn readline_secret<OutputTy, EventsTy, ContentTy, const SIZE: usize>(
+    prompt: &Prompt<ContentTy>,
+    events: EventsTy,
+    output: &mut OutputTy,
+) -> Result<String<SIZE>>
+where
+    OutputTy: io::blocking::Write,
+    EventsTy: Stream<Item = io::Result<Event>>,
+    ContentTy: Iterator + Clone,
+    <ContentTy as Iterator>::Item: fmt::Display,
+{
+    read_line_with(prompt, events, output, true).await
+}
+
+async fn read_line_with<OutputTy, EventsTy, ContentTy, const SIZE: usize>(
+    prompt: &Prompt<ContentTy>,
+    events: EventsTy,
+    output: &mut OutputTy,
+    masked: bool,
+) -> Result<String<SIZE>>
 where
     OutputTy: io::blocking::Write,
     EventsTy: Stream<Item = io::Result<Event>>,
@@ -58,7 +96,10 @@ where
     <ContentTy as Iterator>::Item: fmt::Display,
 {
     // Prepare the output of the line.
-    let mut line: Line<SIZE> = Line::default();
+    let mut line: Line<SIZE> = Line {
+        masked,
+        ..Default::default()
+    };
 
     // Write the prompt, then read for input events.
     prompt.reset(output)?;
@@ -93,14 +134,38 @@ enum LineStatus {
 #[derive(Debug, Default)]
 struct Line<const SIZE: usize = 256> {
     escaped: bool,
+    masked: bool,
     buffer: String<SIZE>,
     cursor: usize,
 }
 
+/// Echo of the line contents, which are hidden if masked.
+struct Echo<'a> {
+    contents: &'a str,
+    masked: bool,
+}
+
+impl fmt::Display for Echo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.masked {
+            return f.write_str(self.contents);
+        }
+
+        self.contents.chars().try_for_each(|_| f.write_str("*"))
+    }
+}
+
 impl<const SIZE: usize> Line<SIZE> {
     const _ASSERT_SIZE_IS_U16_CONVERTIBLE: () =
         assert!(SIZE <= u16::MAX as usize, "SIZE must be less than 65535");
 
+    fn echo<'a>(&self, contents: &'a str) -> Echo<'a> {
+        Echo {
+            contents,
+            masked: self.masked,
+        }
+    }
+
     fn on_key_event<ContentTy, WriterTy>(
         &mut self,
         event: KeyEvent,
@@ -157,7 +222,7 @@ impl<const SIZE: usize> Line<SIZE> {
 
             if !updated.is_empty() {
                 output
-                    .queue(Print(updated))?
+                    .queue(Print(self.echo(updated)))?
                     .queue(MoveLeft(updated.len() as u16))?
                     .flush()?;
             }
@@ -177,14 +242,14 @@ impl<const SIZE: usize> Line<SIZE> {
 
             if is_cursor_eol {
                 self.buffer.push(cased)?;
-                output.execute(Print(cased))?;
+                output.execute(Print(if self.masked { '*' } else { cased }))?;
             } else {
                 self.buffer.insert(self.cursor, cased)?;
                 let (_, updated) = self.buffer.split_at(self.cursor);
 
                 output
                     .queue(Clear(ClearType::LineFromCursor))?
-                    .queue(Print(updated))?
+                    .queue(Print(self.echo(updated)))?
                     .queue(MoveLeft((updated.len() - 1) as u16))?
                     .flush()?;
             };
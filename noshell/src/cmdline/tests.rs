@@ -106,3 +106,65 @@ async fn it_should_read_line(#[context] ctx: Context, #[case] input: &str) {
         insta::assert_snapshot!(result);
     });
 }
+
+#[cfg(feature = "parser")]
+#[tokio::test]
+async fn it_should_ask_missing_args() {
+    use super::lexer;
+    use super::wizard::ask_missing;
+
+    #[derive(Debug, PartialEq, Eq, noshell::Parser)]
+    struct MyArgs {
+        /// Network name.
+        #[arg(short)]
+        ssid: heapless::String<16>,
+
+        #[arg(secret)]
+        password: heapless::String<16>,
+
+        retries: u32,
+
+        channel: Option<u8>,
+    }
+
+    assert_that!(MyArgs::ARGS[0].help()).is_equal_to("Network name.");
+    assert_that!(MyArgs::ARGS[3].is_required()).is_false();
+
+    // The invalid number of retries is asked again.
+    let cmdline = String::from("secret\x0dmany\x0d3\x0d");
+    let mut input = StringBuf::new(cmdline);
+    let mut output = StringBuf::new(String::default());
+
+    let stream = events::stream(&mut input);
+    pin_mut!(stream);
+
+    let argv = ["-s", "home"];
+    let answers: heapless::String<64> = ask_missing::<_, _, 64, 8>(
+        &argv,
+        MyArgs::lookup_table(),
+        MyArgs::ARGS,
+        stream,
+        &mut output,
+    )
+    .await
+    .unwrap();
+
+    assert_that!(answers.as_str()).is_equal_to("--password=secret --retries=3");
+    assert_that!(output.inner.as_str()).does_not_contain("secret");
+    assert_that!(output.inner.as_str()).contains("Enter --password:");
+    assert_that!(output.inner.as_str()).contains("invalid value");
+
+    let words = argv
+        .into_iter()
+        .chain(lexer::split(&answers).map(Result::unwrap))
+        .collect::<Vec<_>>();
+
+    assert_that!(MyArgs::try_parse_from(&words))
+        .is_ok()
+        .is_equal_to(MyArgs {
+            ssid: "home".try_into().unwrap(),
+            password: "secret".try_into().unwrap(),
+            retries: 3,
+            channel: None,
+        });
+}
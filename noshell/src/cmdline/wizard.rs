@@ -0,0 +1,87 @@
+//! Interactive prompting of the missing arguments.
+
+use core::fmt;
+
+use futures::Stream;
+use heapless::String;
+use noterm::Queuable;
+use noterm::cursor::MoveToNextLine;
+use noterm::events::Event;
+use noterm::io;
+use noterm::style::Print;
+
+use crate::cmdline::{Error, Prompt, Result, readline, readline_secret};
+use crate::parser::{ArgInfo, ArgLookupTable, ArgsWriter, ParsedArgs};
+
+/// Ask for the required arguments which are missing from the command line, then return their
+/// values as a command line of their own, which is meant to be split and appended to the input.
+///
+/// Each argument is asked with its flag and its help as a hint (e.g. `Enter --ssid (Network
+/// name):`), and asked again until its value is valid. Secret values are not echoed. Positional
+/// arguments cannot be asked, since they have no flag.
+///
+/// The command line is parsed with at most `N` words, to find the missing arguments.
+pub async fn ask_missing<OutputTy, EventsTy, const SIZE: usize, const N: usize>(
+    argv: &[&str],
+    table: &'static ArgLookupTable<'static>,
+    args: &[ArgInfo<'_>],
+    mut events: EventsTy,
+    output: &mut OutputTy,
+) -> Result<String<SIZE>>
+where
+    OutputTy: io::blocking::Write,
+    EventsTy: Stream<Item = io::Result<Event>> + Unpin,
+{
+    let parsed = ParsedArgs::<'_, N>::try_parse_from(argv.iter().copied(), table)?;
+
+    let mut answers = String::new();
+    let mut writer = ArgsWriter::new(&mut answers);
+
+    for arg in args
+        .iter()
+        .filter(|x| x.is_required() && !parsed.contains(x.id()))
+    {
+        let Some(flag) = arg.flag() else {
+            continue;
+        };
+
+        let hint = Hint(arg.help());
+        let parts: [&dyn fmt::Display; 4] = [&"Enter ", &flag, &hint, &":"];
+        let prompt = Prompt::new(parts.into_iter());
+
+        loop {
+            let value: String<SIZE> = if arg.is_secret() {
+                readline_secret(&prompt, &mut events, output).await?
+            } else {
+                readline(&prompt, &mut events, output).await?
+            };
+
+            if arg.validate(&value) {
+                writer
+                    .flag_with_value(flag, value.as_str())
+                    .map_err(|_| Error::NoSpaceLeft)?;
+                break;
+            }
+
+            output
+                .queue(MoveToNextLine(1))?
+                .queue(Print("invalid value"))?
+                .flush()?;
+        }
+    }
+
+    Ok(answers)
+}
+
+/// Help of an argument, printed between parentheses if any.
+struct Hint<'a>(&'a str);
+
+impl fmt::Display for Hint<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, " ({})", self.0)
+    }
+}
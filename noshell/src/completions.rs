@@ -0,0 +1,448 @@
+//! Generation of the completion scripts of host shells (i.e. bash, zsh and fish).
+//!
+//! The scripts complete the flags, the subcommands and the possible values of the arguments of a
+//! command, described by a [`CommandInfo`], so that host tools built on the same parsers as the
+//! firmware get completions for free. Hidden and deprecated arguments are not completed, and all
+//! the flags of an argument are only known if the look-up table is given (see
+//! [`CommandInfo::with_lookup_table`]).
+
+use core::fmt::{self, Write};
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::parser::lexer::Flag;
+use crate::parser::{ArgInfo, CommandInfo};
+
+/// Defines the supported shells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// Bash.
+    Bash,
+
+    /// Zsh.
+    Zsh,
+
+    /// Fish.
+    Fish,
+}
+
+impl Shell {
+    /// Parse a shell from its name (e.g. `bash`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+}
+
+/// Generate the completion script of a command for the given shell.
+pub fn generate(shell: Shell, cmd: &CommandInfo<'_>) -> String {
+    let mut out = String::new();
+
+    // SAFETY: writing to a string never fails.
+    write(shell, cmd, &mut out).unwrap();
+    out
+}
+
+/// Write the completion script of a command for the given shell.
+pub fn write<W: Write>(shell: Shell, cmd: &CommandInfo<'_>, out: &mut W) -> fmt::Result {
+    let mut nodes = Vec::new();
+    collect_nodes(cmd, Vec::new(), &mut nodes);
+
+    match shell {
+        Shell::Bash => write_bash(cmd.name(), &nodes, out),
+        Shell::Zsh => write_zsh(cmd.name(), &nodes, out),
+        Shell::Fish => write_fish(cmd.name(), &nodes, out),
+    }
+}
+
+/// A command of the tree, with the names of the subcommands leading to it.
+struct Node<'a> {
+    path: Vec<&'a str>,
+    cmd: CommandInfo<'a>,
+}
+
+impl Node<'_> {
+    /// Name of the node in the scripts (e.g. `modem__reset`).
+    fn key(&self) -> String {
+        self.path.join("__")
+    }
+
+    /// Iterate over the arguments to complete.
+    fn args(&self) -> impl Iterator<Item = &ArgInfo<'_>> + '_ {
+        self.cmd.visible_args().filter(|x| x.deprecated().is_none())
+    }
+
+    /// Iterate over the flags to complete, with their argument.
+    fn flags(&self) -> impl Iterator<Item = (Flag<'_>, &ArgInfo<'_>)> + '_ {
+        self.args()
+            .flat_map(|arg| self.cmd.flags_of(arg).map(move |flag| (flag, arg)))
+    }
+
+    /// Iterate over the flags whose values are known, with their argument.
+    fn valued_flags(&self) -> impl Iterator<Item = (Flag<'_>, &ArgInfo<'_>)> + '_ {
+        self.flags()
+            .filter(|(_, arg)| !arg.possible_values().is_empty())
+    }
+
+    /// Iterate over the possible values of the positional arguments.
+    fn positional_values(&self) -> impl Iterator<Item = &str> + '_ {
+        self.args()
+            .filter(|arg| self.cmd.flags_of(arg).next().is_none())
+            .flat_map(|arg| arg.possible_values().iter().copied())
+    }
+}
+
+fn collect_nodes<'a>(cmd: &CommandInfo<'a>, mut path: Vec<&'a str>, nodes: &mut Vec<Node<'a>>) {
+    path.push(cmd.name());
+
+    for sub in cmd.subcommands() {
+        collect_nodes(sub, path.clone(), nodes);
+    }
+
+    nodes.push(Node { path, cmd: *cmd });
+}
+
+fn write_bash<W: Write>(name: &str, nodes: &[Node<'_>], out: &mut W) -> fmt::Result {
+    writeln!(out, "_{}() {{", name)?;
+    writeln!(out, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"")?;
+    writeln!(out, "    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"")?;
+    writeln!(out, "    local cmd=\"{}\"", name)?;
+    writeln!(out, "    local opts i\n")?;
+
+    writeln!(out, "    for ((i = 1; i < COMP_CWORD; i++)); do")?;
+    writeln!(out, "        case \"${{cmd}}:${{COMP_WORDS[i]}}\" in")?;
+
+    for node in nodes {
+        for sub in node.cmd.subcommands() {
+            writeln!(
+                out,
+                "            {}) cmd={} ;;",
+                Quoted(&format!("{}:{}", node.key(), sub.name())),
+                Quoted(&format!("{}__{}", node.key(), sub.name())),
+            )?;
+        }
+    }
+
+    writeln!(out, "        esac")?;
+    writeln!(out, "    done\n")?;
+
+    // The values of a flag are completed right after it.
+    writeln!(out, "    case \"${{cmd}}:${{prev}}\" in")?;
+
+    for node in nodes {
+        for (flag, arg) in node.valued_flags() {
+            writeln!(
+                out,
+                "        {}) COMPREPLY=($(compgen -W {} -- \"${{cur}}\")); return ;;",
+                Quoted(&format!("{}:{}", node.key(), flag)),
+                Quoted(&arg.possible_values().join(" ")),
+            )?;
+        }
+    }
+
+    writeln!(out, "    esac\n")?;
+
+    writeln!(out, "    case \"${{cmd}}\" in")?;
+
+    for node in nodes {
+        let words = node
+            .flags()
+            .map(|(flag, _)| format!("{}", flag))
+            .chain(node.positional_values().map(String::from))
+            .chain(
+                node.cmd
+                    .subcommands()
+                    .iter()
+                    .map(|x| String::from(x.name())),
+            )
+            .collect::<Vec<_>>();
+
+        writeln!(
+            out,
+            "        {}) opts={} ;;",
+            Quoted(&node.key()),
+            Quoted(&words.join(" "))
+        )?;
+    }
+
+    writeln!(out, "    esac\n")?;
+
+    writeln!(
+        out,
+        "    COMPREPLY=($(compgen -W \"${{opts}}\" -- \"${{cur}}\"))"
+    )?;
+    writeln!(out, "}}\n")?;
+    writeln!(out, "complete -o default -F _{} {}", name, name)
+}
+
+fn write_zsh<W: Write>(name: &str, nodes: &[Node<'_>], out: &mut W) -> fmt::Result {
+    writeln!(out, "#compdef {}\n", name)?;
+
+    writeln!(out, "_{}() {{", name)?;
+    writeln!(out, "    local cmd=\"{}\" i", name)?;
+    writeln!(out, "    local -a opts\n")?;
+
+    writeln!(out, "    for ((i = 2; i < CURRENT; i++)); do")?;
+    writeln!(out, "        case \"${{cmd}}:${{words[i]}}\" in")?;
+
+    for node in nodes {
+        for sub in node.cmd.subcommands() {
+            writeln!(
+                out,
+                "            {}) cmd={} ;;",
+                Quoted(&format!("{}:{}", node.key(), sub.name())),
+                Quoted(&format!("{}__{}", node.key(), sub.name())),
+            )?;
+        }
+    }
+
+    writeln!(out, "        esac")?;
+    writeln!(out, "    done\n")?;
+
+    // The values of a flag are completed right after it.
+    writeln!(out, "    case \"${{cmd}}:${{words[CURRENT-1]}}\" in")?;
+
+    for node in nodes {
+        for (flag, arg) in node.valued_flags() {
+            let values = arg
+                .possible_values()
+                .iter()
+                .map(|x| Quoted(x).to_string())
+                .collect::<Vec<_>>();
+
+            writeln!(
+                out,
+                "        {}) _values {} {}; return ;;",
+                Quoted(&format!("{}:{}", node.key(), flag)),
+                Quoted(arg.id()),
+                values.join(" "),
+            )?;
+        }
+    }
+
+    writeln!(out, "    esac\n")?;
+
+    writeln!(out, "    case \"${{cmd}}\" in")?;
+
+    for node in nodes {
+        let words = node
+            .flags()
+            .map(|(flag, arg)| Described(&format!("{}", flag), arg.help()).to_string())
+            .chain(
+                node.positional_values()
+                    .map(|x| Described(x, "").to_string()),
+            )
+            .chain(
+                node.cmd
+                    .subcommands()
+                    .iter()
+                    .map(|x| Described(x.name(), x.about()).to_string()),
+            )
+            .map(|x| Quoted(&x).to_string())
+            .collect::<Vec<_>>();
+
+        writeln!(
+            out,
+            "        {}) opts=({}) ;;",
+            Quoted(&node.key()),
+            words.join(" ")
+        )?;
+    }
+
+    writeln!(out, "    esac\n")?;
+
+    writeln!(out, "    _describe {} opts", Quoted(name))?;
+    writeln!(out, "}}\n")?;
+
+    writeln!(out, "if [ \"$funcstack[1]\" = \"_{}\" ]; then", name)?;
+    writeln!(out, "    _{} \"$@\"", name)?;
+    writeln!(out, "else")?;
+    writeln!(out, "    compdef _{} {}", name, name)?;
+    writeln!(out, "fi")
+}
+
+fn write_fish<W: Write>(name: &str, nodes: &[Node<'_>], out: &mut W) -> fmt::Result {
+    writeln!(out, "complete -c {} -f", name)?;
+
+    for node in nodes {
+        let condition = FishCondition(node);
+
+        for sub in node.cmd.subcommands() {
+            write!(
+                out,
+                "complete -c {}{} -a {}",
+                name,
+                condition,
+                Quoted(sub.name())
+            )?;
+
+            if !sub.about().is_empty() {
+                write!(out, " -d {}", Quoted(sub.about()))?;
+            }
+
+            writeln!(out)?;
+        }
+
+        let values = node.positional_values().collect::<Vec<_>>();
+
+        if !values.is_empty() {
+            writeln!(
+                out,
+                "complete -c {}{} -a {}",
+                name,
+                condition,
+                Quoted(&values.join(" "))
+            )?;
+        }
+
+        for (flag, arg) in node.flags() {
+            match flag {
+                Flag::Short(x) => write!(out, "complete -c {}{} -s {}", name, condition, x)?,
+                Flag::Long(x) => write!(out, "complete -c {}{} -l {}", name, condition, Quoted(x))?,
+            }
+
+            if !arg.help().is_empty() {
+                write!(out, " -d {}", Quoted(arg.help()))?;
+            }
+
+            // All the flags expect a value, which may be known.
+            write!(out, " -r")?;
+
+            if !arg.possible_values().is_empty() {
+                write!(out, " -a {}", Quoted(&arg.possible_values().join(" ")))?;
+            }
+
+            writeln!(out)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Condition of the fish completions of a command: its subcommands have been given, but not its
+/// own subcommands.
+struct FishCondition<'a, 'b>(&'a Node<'b>);
+
+impl fmt::Display for FishCondition<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut conditions = self.0.path[1..]
+            .iter()
+            .map(|x| format!("__fish_seen_subcommand_from {}", x))
+            .collect::<Vec<_>>();
+
+        let subcommands = self.0.cmd.subcommands();
+
+        if !subcommands.is_empty() {
+            let names = subcommands.iter().map(|x| x.name()).collect::<Vec<_>>();
+            conditions.push(format!(
+                "not __fish_seen_subcommand_from {}",
+                names.join(" ")
+            ));
+        }
+
+        if conditions.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, " -n {}", Quoted(&conditions.join("; and ")))
+    }
+}
+
+/// Word described for zsh (e.g. `--baud:Baud rate`), whose colons are escaped.
+struct Described<'a>(&'a str, &'a str);
+
+impl fmt::Display for Described<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.replace(':', "\\:"))?;
+
+        if !self.1.is_empty() {
+            write!(f, ":{}", self.1)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Text between single quotes, so that it is given verbatim to the shell.
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}'", self.0.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use speculoos::prelude::*;
+
+    use crate::parser::{ArgInfo, ArgLookupTable, AtMost};
+
+    use super::*;
+
+    static MODEM_ARGS: &[ArgInfo<'static>] = &[
+        ArgInfo::new("baud")
+            .with_flag(Flag::Long("baud"))
+            .with_help("Baud rate of the link."),
+        ArgInfo::new("parity")
+            .with_flag(Flag::Long("parity"))
+            .with_help("Parity of the link.")
+            .with_possible_values(&["none", "even", "odd"]),
+        ArgInfo::new("baud_rate")
+            .with_flag(Flag::Long("baud_rate"))
+            .with_deprecated("use --baud"),
+        ArgInfo::new("factory")
+            .with_flag(Flag::Long("factory"))
+            .with_hidden(true),
+    ];
+
+    static MODEM_TABLE: ArgLookupTable<'static> = ArgLookupTable::new(&[
+        (Flag::Short('b'), "baud", AtMost::One),
+        (Flag::Long("baud"), "baud", AtMost::One),
+        (Flag::Long("baud_rate"), "baud_rate", AtMost::One),
+        (Flag::Long("parity"), "parity", AtMost::One),
+        (Flag::Long("factory"), "factory", AtMost::One),
+    ]);
+
+    static RESET_ARGS: &[ArgInfo<'static>] = &[
+        ArgInfo::new("delay")
+            .with_flag(Flag::Long("delay"))
+            .with_help("Delay in milliseconds, or 'now'."),
+        ArgInfo::new("mode").with_possible_values(&["soft", "hard"]),
+    ];
+
+    static MODEM: CommandInfo<'static> = CommandInfo::new("modem", MODEM_ARGS)
+        .with_about("Configure the modem.")
+        .with_lookup_table(&MODEM_TABLE)
+        .with_subcommands(&[CommandInfo::new("reset", RESET_ARGS).with_about("Reset the modem.")]);
+
+    #[test]
+    fn it_should_generate_bash_completions() {
+        let output = generate(Shell::Bash, &MODEM);
+
+        insta::assert_snapshot!(output);
+        assert_that!(output.contains("factory")).is_false();
+        assert_that!(output.contains("baud_rate")).is_false();
+    }
+
+    #[test]
+    fn it_should_generate_zsh_completions() {
+        insta::assert_snapshot!(generate(Shell::Zsh, &MODEM));
+    }
+
+    #[test]
+    fn it_should_generate_fish_completions() {
+        insta::assert_snapshot!(generate(Shell::Fish, &MODEM));
+    }
+
+    #[test]
+    fn it_should_parse_shell_names() {
+        assert_that!(Shell::from_name("zsh")).is_equal_to(Some(Shell::Zsh));
+        assert_that!(Shell::from_name("powershell")).is_none();
+    }
+}
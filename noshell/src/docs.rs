@@ -0,0 +1,518 @@
+//! Generation of the documentation of commands, as Markdown or as man pages.
+//!
+//! The documentation is generated from the same metadata as the parsers (e.g. from a `build.rs`
+//! or a test), so that the manual of the firmware always matches its commands. Hidden arguments
+//! are left out. A command is described by a [`CommandInfo`] (e.g.
+//! `CommandInfo::new("modem", MyArgs::ARGS).with_about(MyArgs::ABOUT)`).
+
+use core::fmt::{self, Write};
+use std::format;
+use std::path::Path;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::parser::{ArgInfo, CommandInfo};
+
+/// Generate the Markdown documentation of a command and all its subcommands.
+pub fn markdown(cmd: &CommandInfo<'_>) -> String {
+    let mut out = String::new();
+
+    // SAFETY: writing to a string never fails.
+    write_markdown(cmd, &mut out).unwrap();
+    out
+}
+
+/// Write the Markdown documentation of a command and all its subcommands.
+pub fn write_markdown<W: Write>(cmd: &CommandInfo<'_>, out: &mut W) -> fmt::Result {
+    write_markdown_with(cmd, cmd.name(), 1, out)
+}
+
+fn write_markdown_with<W: Write>(
+    cmd: &CommandInfo<'_>,
+    path: &str,
+    level: usize,
+    out: &mut W,
+) -> fmt::Result {
+    let heading = "#".repeat(level);
+    let subheading = "#".repeat(level + 1);
+
+    writeln!(out, "{} {}\n", heading, path)?;
+
+    if !cmd.about().is_empty() {
+        writeln!(out, "{}\n", cmd.about())?;
+    }
+
+    writeln!(out, "{} Usage\n", subheading)?;
+    writeln!(out, "```text\n{}{}\n```\n", path, Synopsis(cmd))?;
+
+    let (positionals, flags) = split_args(cmd);
+
+    if !positionals.is_empty() {
+        writeln!(out, "{} Arguments\n", subheading)?;
+
+        for arg in positionals {
+            write!(out, "- `{}`", Placeholder(arg))?;
+            write_markdown_help(arg, out)?;
+        }
+
+        writeln!(out)?;
+    }
+
+    if !flags.is_empty() {
+        writeln!(out, "{} Options\n", subheading)?;
+
+        for arg in flags {
+            write!(out, "- `{}`", Usage(arg))?;
+            write_markdown_help(arg, out)?;
+        }
+
+        writeln!(out)?;
+    }
+
+    if !cmd.subcommands().is_empty() {
+        writeln!(out, "{} Commands\n", subheading)?;
+
+        for sub in cmd.subcommands() {
+            write!(out, "- `{}`", sub.name())?;
+
+            if !sub.about().is_empty() {
+                write!(out, ": {}", sub.about())?;
+            }
+
+            writeln!(out)?;
+        }
+
+        writeln!(out)?;
+
+        for sub in cmd.subcommands() {
+            let path = format!("{} {}", path, sub.name());
+            write_markdown_with(sub, &path, level + 1, out)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_markdown_help<W: Write>(arg: &ArgInfo<'_>, out: &mut W) -> fmt::Result {
+    if arg.is_required() {
+        write!(out, " (required)")?;
+    }
+
+    if !arg.help().is_empty() {
+        write!(out, ": {}", arg.help())?;
+    }
+
+    if let Some(note) = arg.deprecated() {
+        write!(out, " *Deprecated")?;
+
+        if !note.is_empty() {
+            write!(out, ": {}", note)?;
+        }
+
+        write!(out, ".*")?;
+    }
+
+    writeln!(out)
+}
+
+/// Generate the usage of a command, as printed on a terminal after a usage error (e.g.
+/// `Usage: modem [OPTIONS] <COMMAND>`).
+pub fn usage(cmd: &CommandInfo<'_>) -> String {
+    format!("Usage: {}{}", cmd.name(), Synopsis(cmd))
+}
+
+/// Generate the help of a command, as printed on a terminal: its description, its usage, and
+/// its arguments and subcommands.
+pub fn help(cmd: &CommandInfo<'_>) -> String {
+    let mut out = String::new();
+
+    // SAFETY: writing to a string never fails.
+    write_help(cmd, &mut out).unwrap();
+    out
+}
+
+/// Write the help of a command, as printed on a terminal.
+pub fn write_help<W: Write>(cmd: &CommandInfo<'_>, out: &mut W) -> fmt::Result {
+    if !cmd.about().is_empty() {
+        writeln!(out, "{}\n", cmd.about())?;
+    }
+
+    writeln!(out, "{}", usage(cmd))?;
+
+    let (positionals, flags) = split_args(cmd);
+
+    // The help of all the items is aligned on the longest one.
+    let positionals = positionals
+        .into_iter()
+        .map(|x| (Placeholder(x).to_string(), x))
+        .collect::<Vec<_>>();
+    let flags = flags
+        .into_iter()
+        .map(|x| (Usage(x).to_string(), x))
+        .collect::<Vec<_>>();
+
+    let width = positionals
+        .iter()
+        .chain(&flags)
+        .map(|(x, _)| x.len())
+        .chain(cmd.subcommands().iter().map(|x| x.name().len()))
+        .max()
+        .unwrap_or_default();
+
+    for (title, items) in [("Arguments", &positionals), ("Options", &flags)] {
+        if items.is_empty() {
+            continue;
+        }
+
+        writeln!(out, "\n{}:", title)?;
+
+        for (usage, arg) in items {
+            let line = format!("  {:<width$}  {}", usage, TextHelp(arg));
+            writeln!(out, "{}", line.trim_end())?;
+        }
+    }
+
+    if !cmd.subcommands().is_empty() {
+        writeln!(out, "\nCommands:")?;
+
+        for sub in cmd.subcommands() {
+            let line = format!("  {:<width$}  {}", sub.name(), sub.about());
+            writeln!(out, "{}", line.trim_end())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Generate the man page of a command, in the given section (e.g. `1` for user commands). The
+/// subcommands are only listed, since each of them has its own page (see [`man_pages`]).
+pub fn man(cmd: &CommandInfo<'_>, section: u8) -> String {
+    let mut out = String::new();
+
+    // SAFETY: writing to a string never fails.
+    write_man(cmd, section, &mut out).unwrap();
+    out
+}
+
+/// Write the man page of a command, in the given section.
+pub fn write_man<W: Write>(cmd: &CommandInfo<'_>, section: u8, out: &mut W) -> fmt::Result {
+    write_man_with(cmd, cmd.name(), section, out)
+}
+
+/// Generate the man pages of a command and all its subcommands, along with their names (e.g.
+/// `modem-reset` for the `reset` subcommand of `modem`).
+pub fn man_pages(cmd: &CommandInfo<'_>, section: u8) -> Vec<(String, String)> {
+    let mut pages = Vec::new();
+    collect_man_pages(cmd, cmd.name(), section, &mut pages);
+    pages
+}
+
+fn collect_man_pages(
+    cmd: &CommandInfo<'_>,
+    path: &str,
+    section: u8,
+    pages: &mut Vec<(String, String)>,
+) {
+    let mut page = String::new();
+
+    // SAFETY: writing to a string never fails.
+    write_man_with(cmd, path, section, &mut page).unwrap();
+    pages.push((path.replace(' ', "-"), page));
+
+    for sub in cmd.subcommands() {
+        let path = format!("{} {}", path, sub.name());
+        collect_man_pages(sub, &path, section, pages);
+    }
+}
+
+fn write_man_with<W: Write>(
+    cmd: &CommandInfo<'_>,
+    path: &str,
+    section: u8,
+    out: &mut W,
+) -> fmt::Result {
+    let title = path.replace(' ', "-").to_uppercase();
+    writeln!(out, ".TH {} {}", Roff(&title), section)?;
+
+    writeln!(out, ".SH NAME")?;
+    write!(out, "{}", Roff(&path.replace(' ', "-")))?;
+
+    if !cmd.about().is_empty() {
+        write!(out, " \\- {}", Roff(cmd.about()))?;
+    }
+
+    writeln!(out)?;
+
+    writeln!(out, ".SH SYNOPSIS")?;
+    writeln!(
+        out,
+        "\\fB{}\\fR{}",
+        Roff(path),
+        Roff(&Synopsis(cmd).to_string())
+    )?;
+
+    let (positionals, flags) = split_args(cmd);
+
+    if !positionals.is_empty() {
+        writeln!(out, ".SH ARGUMENTS")?;
+
+        for arg in positionals {
+            writeln!(out, ".TP\n\\fI{}\\fR", Roff(&Placeholder(arg).to_string()))?;
+            write_man_help(arg, out)?;
+        }
+    }
+
+    if !flags.is_empty() {
+        writeln!(out, ".SH OPTIONS")?;
+
+        for arg in flags {
+            // SAFETY: the flag arguments have a flag.
+            let flag = arg.flag().unwrap();
+            writeln!(
+                out,
+                ".TP\n\\fB{}\\fR \\fI{}\\fR",
+                Roff(&flag.to_string()),
+                Roff(&ValueName(arg).to_string())
+            )?;
+            write_man_help(arg, out)?;
+        }
+    }
+
+    if !cmd.subcommands().is_empty() {
+        writeln!(out, ".SH COMMANDS")?;
+
+        for sub in cmd.subcommands() {
+            writeln!(out, ".TP\n\\fB{}\\fR", Roff(sub.name()))?;
+
+            if !sub.about().is_empty() {
+                writeln!(out, "{}", Roff(sub.about()))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_man_help<W: Write>(arg: &ArgInfo<'_>, out: &mut W) -> fmt::Result {
+    let mut lines = Vec::new();
+
+    if !arg.help().is_empty() {
+        lines.push(String::from(arg.help()));
+    }
+
+    if arg.is_required() {
+        lines.push(String::from("Required."));
+    }
+
+    match arg.deprecated() {
+        Some("") => lines.push(String::from("Deprecated.")),
+        Some(note) => lines.push(format!("Deprecated: {}.", note)),
+        None => {}
+    }
+
+    for line in lines {
+        writeln!(out, "{}", Roff(&line))?;
+    }
+
+    Ok(())
+}
+
+/// Write the documentation of a command and all its subcommands into a directory: the Markdown
+/// documentation as `<name>.md`, and the man pages as `<name>.<section>`.
+pub fn write_all<P: AsRef<Path>>(
+    cmd: &CommandInfo<'_>,
+    section: u8,
+    dir: P,
+) -> std::io::Result<()> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+
+    std::fs::write(dir.join(format!("{}.md", cmd.name())), markdown(cmd))?;
+
+    for (name, page) in man_pages(cmd, section) {
+        std::fs::write(dir.join(format!("{}.{}", name, section)), page)?;
+    }
+
+    Ok(())
+}
+
+/// Split the visible arguments of a command into the positional ones and the flags.
+fn split_args<'a>(cmd: &CommandInfo<'a>) -> (Vec<&'a ArgInfo<'a>>, Vec<&'a ArgInfo<'a>>) {
+    cmd.visible_args().partition(|x| x.flag().is_none())
+}
+
+/// Usage of a command after its name (e.g. ` [OPTIONS] <input> <COMMAND>`).
+struct Synopsis<'a, 'b>(&'a CommandInfo<'b>);
+
+impl fmt::Display for Synopsis<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (positionals, flags) = split_args(self.0);
+
+        if !flags.is_empty() {
+            write!(f, " [OPTIONS]")?;
+        }
+
+        for arg in positionals {
+            write!(f, " {}", Placeholder(arg))?;
+        }
+
+        if !self.0.subcommands().is_empty() {
+            write!(f, " <COMMAND>")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Placeholder of a positional argument, between angle brackets if required, or square brackets
+/// otherwise.
+struct Placeholder<'a, 'b>(&'a ArgInfo<'b>);
+
+impl fmt::Display for Placeholder<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_required() {
+            write!(f, "<{}>", self.0.id())
+        } else {
+            write!(f, "[{}]", self.0.id())
+        }
+    }
+}
+
+/// Name of the value of a flag, which is its identifier in upper case.
+struct ValueName<'a, 'b>(&'a ArgInfo<'b>);
+
+impl fmt::Display for ValueName<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.0.id().to_uppercase())
+    }
+}
+
+/// Usage of a flag with its value (e.g. `--baud <BAUD>`).
+struct Usage<'a, 'b>(&'a ArgInfo<'b>);
+
+impl fmt::Display for Usage<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.flag() {
+            Some(flag) => write!(f, "{} {}", flag, ValueName(self.0)),
+            None => write!(f, "{}", Placeholder(self.0)),
+        }
+    }
+}
+
+/// Help of an argument on a terminal (e.g. `Baud rate of the link. [required]`).
+struct TextHelp<'a, 'b>(&'a ArgInfo<'b>);
+
+impl fmt::Display for TextHelp<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut notes = Vec::new();
+
+        if !self.0.help().is_empty() {
+            notes.push(String::from(self.0.help()));
+        }
+
+        if self.0.is_required() {
+            notes.push(String::from("[required]"));
+        }
+
+        if !self.0.possible_values().is_empty() {
+            notes.push(format!(
+                "[possible values: {}]",
+                self.0.possible_values().join(", ")
+            ));
+        }
+
+        match self.0.deprecated() {
+            Some("") => notes.push(String::from("[deprecated]")),
+            Some(note) => notes.push(format!("[deprecated: {}]", note)),
+            None => {}
+        }
+
+        f.write_str(&notes.join(" "))
+    }
+}
+
+/// Text escaped for roff, so that it is printed verbatim.
+struct Roff<'a>(&'a str);
+
+impl fmt::Display for Roff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A line starting with a dot or a quote would be a request.
+        if self.0.starts_with(['.', '\'']) {
+            f.write_str("\\&")?;
+        }
+
+        for c in self.0.chars() {
+            match c {
+                '\\' => f.write_str("\\e")?,
+                '-' => f.write_str("\\-")?,
+                _ => f.write_char(c)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use speculoos::prelude::*;
+
+    use crate::parser::lexer::Flag;
+
+    use super::*;
+
+    static MODEM_ARGS: &[ArgInfo<'static>] = &[
+        ArgInfo::new("baud")
+            .with_flag(Flag::Long("baud"))
+            .with_help("Baud rate of the link.")
+            .with_required(true),
+        ArgInfo::new("baud_rate")
+            .with_flag(Flag::Long("baud_rate"))
+            .with_deprecated("use --baud"),
+        ArgInfo::new("factory")
+            .with_flag(Flag::Long("factory"))
+            .with_hidden(true),
+    ];
+
+    static RESET_ARGS: &[ArgInfo<'static>] =
+        &[ArgInfo::new("delay").with_help("Delay in milliseconds.")];
+
+    static MODEM: CommandInfo<'static> = CommandInfo::new("modem", MODEM_ARGS)
+        .with_about("Configure the modem.")
+        .with_subcommands(&[CommandInfo::new("reset", RESET_ARGS).with_about("Reset the modem.")]);
+
+    #[test]
+    fn it_should_generate_markdown() {
+        let output = markdown(&MODEM);
+
+        insta::assert_snapshot!(output);
+        assert_that!(output.contains("factory")).is_false();
+    }
+
+    #[test]
+    fn it_should_generate_man_pages() {
+        let pages = man_pages(&MODEM, 1);
+        let names = pages.iter().map(|(x, _)| x.as_str()).collect::<Vec<_>>();
+
+        assert_that!(names).is_equal_to(std::vec!["modem", "modem-reset"]);
+        assert_that!(pages[0].1.contains("factory")).is_false();
+
+        insta::assert_snapshot!(pages[0].1);
+        insta::assert_snapshot!(pages[1].1);
+    }
+
+    #[test]
+    fn it_should_generate_help() {
+        let output = help(&MODEM);
+
+        insta::assert_snapshot!(output);
+        assert_that!(output.contains("factory")).is_false();
+        assert_that!(usage(&MODEM)).is_equal_to(String::from("Usage: modem [OPTIONS] <COMMAND>"));
+    }
+
+    #[test]
+    fn it_should_escape_roff() {
+        assert_that!(Roff(".TH --x \\").to_string())
+            .is_equal_to(String::from("\\&.TH \\-\\-x \\e"));
+    }
+}
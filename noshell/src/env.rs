@@ -0,0 +1,159 @@
+//! Integration with the standard library, for parsing process arguments on a host.
+
+use std::ffi::OsString;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::Error;
+use crate::docs;
+use crate::parser::CommandInfo;
+
+/// Exit code on usage errors (e.g. an undefined flag or an invalid value), as returned by most
+/// command line tools.
+pub const EXIT_USAGE: i32 = 2;
+
+/// Exit code after printing the help, which is not an error.
+pub const EXIT_SUCCESS: i32 = 0;
+
+/// Get the name of the program, which is the file name of `argv[0]` (e.g. `modem` for
+/// `/usr/bin/modem`), so that the help matches how the program is called.
+pub fn program_name() -> String {
+    std::env::args_os()
+        .next()
+        .map(std::path::PathBuf::from)
+        .and_then(|x| x.file_name().map(|x| x.to_string_lossy().into_owned()))
+        .unwrap_or_default()
+}
+
+/// Collect the process arguments as UTF-8 strings, skipping the program name (i.e. `argv[0]`).
+///
+/// Returns `Error::Utf8` if any argument is not a valid UTF-8 string.
+pub fn args() -> Result<Vec<String>, Error> {
+    args_from(std::env::args_os().skip(1))
+}
+
+/// Convert the given OS strings to UTF-8 strings, failing on the first invalid one.
+pub fn args_from<I>(iter: I) -> Result<Vec<String>, Error>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    iter.into_iter()
+        .map(|x| x.into_string().map_err(|_| Error::Utf8))
+        .collect()
+}
+
+/// Collect the process arguments, then pass them to the given parser.
+pub fn try_parse_with<T, F>(parser: F) -> Result<T, Error>
+where
+    F: FnOnce(&[&str]) -> Result<T, Error>,
+{
+    let args = args()?;
+    let argv = args.iter().map(String::as_str).collect::<Vec<_>>();
+    parser(&argv)
+}
+
+/// Collect the process arguments, then pass them to the given parser, or exit the process.
+///
+/// If the parser fails while the help is requested (i.e. with `-h` or `--help`, which the command
+/// does not define), then the help of the command is printed to the standard output, and the
+/// process exits with code [`EXIT_SUCCESS`]. On any other error, the error and the usage of the
+/// command are printed to the standard error, and the process exits with code [`EXIT_USAGE`].
+pub fn parse_or_exit<T, F>(cmd: &CommandInfo<'_>, parser: F) -> T
+where
+    F: FnOnce(&[&str]) -> Result<T, Error>,
+{
+    let args = match args() {
+        Ok(args) => args,
+        Err(err) => exit_with_usage(&err, cmd),
+    };
+
+    let argv = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match parser(&argv) {
+        Ok(x) => x,
+        Err(_) if is_help_requested(&argv) => {
+            std::print!("{}", docs::help(cmd));
+            std::process::exit(EXIT_SUCCESS)
+        }
+        Err(err) => exit_with_usage(&err, cmd),
+    }
+}
+
+/// Check if the help is requested by any word before the end of the options (i.e. `--`).
+fn is_help_requested(argv: &[&str]) -> bool {
+    argv.iter()
+        .take_while(|&&x| x != "--")
+        .any(|&x| x == "-h" || x == "--help")
+}
+
+/// Print the error and the usage of the command to the standard error, and exit the process with
+/// code [`EXIT_USAGE`].
+pub fn exit_with_usage(err: &Error, cmd: &CommandInfo<'_>) -> ! {
+    std::eprintln!("error: {}\n", err);
+    std::eprintln!("{}\n", docs::usage(cmd));
+    std::eprintln!("For more information, try `--help`.");
+    std::process::exit(EXIT_USAGE)
+}
+
+/// Unwrap the result of parsing, or print the error to the standard error and exit the process
+/// with code [`EXIT_USAGE`]. The usage is only printed with [`exit_with_usage`], since the command
+/// is not known here.
+pub fn exit_on_error<T>(res: Result<T, Error>) -> T {
+    match res {
+        Ok(x) => x,
+        Err(err) => exit(&err),
+    }
+}
+
+/// Print the error to the standard error and exit the process with code [`EXIT_USAGE`].
+pub fn exit(err: &Error) -> ! {
+    std::eprintln!("error: {}", err);
+    std::process::exit(EXIT_USAGE)
+}
+
+/// Print a warning to the standard error about a deprecated argument, which is meant to be given
+/// with `#[noshell(deprecation_hook = noshell::env::warn_deprecated)]`.
+pub fn warn_deprecated(id: &str, note: &str) {
+    if note.is_empty() {
+        std::eprintln!("warning: `{}` is deprecated", id);
+    } else {
+        std::eprintln!("warning: `{}` is deprecated, {}", id, note);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use speculoos::prelude::*;
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn it_should_reject_non_utf8_args() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let argv = [
+            OsString::from("--value"),
+            OsString::from_vec(std::vec![0xff, 0xfe]),
+        ];
+
+        assert_that!(args_from(argv)).is_err_containing(Error::Utf8);
+    }
+
+    #[test]
+    fn it_should_detect_help_before_end_of_options() {
+        assert_that!(is_help_requested(&["--value", "1", "--help"])).is_true();
+        assert_that!(is_help_requested(&["-h"])).is_true();
+        assert_that!(is_help_requested(&["--", "--help"])).is_false();
+        assert_that!(is_help_requested(&["--helper"])).is_false();
+    }
+
+    #[test]
+    fn it_should_collect_utf8_args() {
+        let argv = [OsString::from("--value"), OsString::from("42")];
+
+        assert_that!(args_from(argv))
+            .is_ok()
+            .is_equal_to(std::vec![String::from("--value"), String::from("42")]);
+    }
+}
@@ -1,19 +1,35 @@
 //! noshell, a `no_std` argument parser and a shell for constrained systems.
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![allow(async_fn_in_trait)]
 #![deny(missing_docs)]
 
 #[cfg(feature = "parser")]
-pub use {macros::Parser, noshell_macros as macros, noshell_parser as parser};
+pub use {
+    macros::{Parser, ValueEnum},
+    noshell_macros as macros, noshell_parser as parser,
+};
 
 #[cfg(feature = "events")]
 pub use noterm::events;
 
 pub mod cmdline;
 
+#[cfg(feature = "std")]
+pub mod completions;
+
+#[cfg(feature = "std")]
+pub mod docs;
+
+#[cfg(feature = "std")]
+pub mod env;
+
 #[cfg(test)]
 mod tests;
 
+// The derived code refers to the crate by its name, as in the crates which use it.
+#[cfg(all(test, feature = "parser"))]
+extern crate self as noshell;
+
 /// Defines the possible errors that may occur during usage of the crate.
 #[derive(Debug, PartialEq, Eq, thiserror::Error)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -23,7 +23,7 @@ fn it_should_parse_args_with_option_type() {
         value: Option<u32>,
     }
 
-    let argv = &[];
+    let argv: &[&str] = &[];
     let output = MyArgs::try_parse_from(argv);
 
     assert_that!(output).is_ok().map(|x| &x.value).is_none();
@@ -45,7 +45,7 @@ fn it_should_parse_args_with_option_option_type() {
         value: Option<Option<u32>>,
     }
 
-    let argv = &[];
+    let argv: &[&str] = &[];
     let output = MyArgs::try_parse_from(argv);
 
     assert_that!(output).is_ok().map(|x| &x.value).is_none();
@@ -70,7 +70,7 @@ fn it_should_parse_args_with_option_vec_type() {
     }
 
     // No argument.
-    let argv = &[];
+    let argv: &[&str] = &[];
     let output = MyArgs::try_parse_from(argv);
 
     assert_that!(output).is_ok().map(|x| &x.value).is_none();
@@ -128,7 +128,7 @@ fn it_should_parse_args_with_vec_type() {
     }
 
     // No argument.
-    let argv = &[];
+    let argv: &[&str] = &[];
     let output = MyArgs::try_parse_from(argv);
 
     assert_that!(output).is_err();
@@ -173,3 +173,1046 @@ fn it_should_panic_at_parsing_args_with_vec_type() {
     let argv = &["--value", "1", "2", "3", "4", "5"];
     let _ = MyArgs::try_parse_from(argv);
 }
+
+#[test]
+fn it_should_parse_args_from_cmdline_lexer() {
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        value: u32,
+        other: Option<u32>,
+    }
+
+    let argv = noshell::cmdline::lexer::split("--value 42 --other \"23\"");
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.value == 42 && x.other == Some(23));
+}
+
+#[test]
+fn it_should_parse_args_from_owned_words() {
+    use heapless::String;
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        value: u32,
+    }
+
+    let argv = ["--value", "42"].map(|x| String::<8>::try_from(x).unwrap());
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .map(|x| &x.value)
+        .is_equal_to(42);
+}
+
+#[test]
+fn it_should_collect_raw_args() {
+    use heapless::{String, Vec};
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        port: u32,
+
+        #[arg(raw)]
+        rest: Vec<String<16>, 8>,
+    }
+
+    let argv = &["--baud", "9600", "--port", "2", "--echo"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().matches(|x| {
+        x.port == 2
+            && x.rest
+                .iter()
+                .map(String::as_str)
+                .eq(["--baud", "9600", "--echo"])
+    });
+}
+
+#[test]
+fn it_should_ignore_undefined_args() {
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(undefined = "ignore")]
+    struct MyArgs {
+        port: u32,
+    }
+
+    let argv = &["--baud", "9600", "--port", "2"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().map(|x| &x.port).is_equal_to(2);
+}
+
+#[test]
+fn it_should_parse_trailing_var_args() {
+    use heapless::{String, Vec};
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        count: u32,
+
+        #[arg(trailing_var_arg)]
+        command: Vec<String<16>, 8>,
+    }
+
+    let argv = &["--count", "5", "echo", "--count", "-n"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().matches(|x| {
+        x.count == 5
+            && x.command
+                .iter()
+                .map(String::as_str)
+                .eq(["echo", "--count", "-n"])
+    });
+
+    // Missing trailing var args.
+    let argv = &["--count", "5"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_err();
+}
+
+#[test]
+fn it_should_parse_borrowed_trailing_var_args_with_flag() {
+    use heapless::Vec;
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs<'a> {
+        core: u32,
+
+        #[arg(long, trailing_var_arg)]
+        exec: Option<Vec<&'a str, 8>>,
+    }
+
+    let argv = &["--core", "1", "--exec", "reboot", "-f"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.core == 1 && x.exec.as_deref() == Some(&["reboot", "-f"][..]));
+
+    let argv = &["--core", "1"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().matches(|x| x.exec.is_none());
+}
+
+#[test]
+fn it_should_parse_trailing_var_args_after_positional_args() {
+    use heapless::{String, Vec};
+
+    #[derive(Debug, noshell::Parser)]
+    struct Repeat(u32, #[arg(trailing_var_arg)] Vec<String<16>, 8>);
+
+    let argv = &["5", "echo", "--x"];
+    let output = Repeat::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.0 == 5 && x.1.iter().map(String::as_str).eq(["echo", "--x"]));
+
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(to_args)]
+    struct ExecOn<'a>(String<8>, #[arg(trailing_var_arg)] Vec<&'a str, 8>);
+
+    let argv = &["core1", "reboot", "-f"];
+    let output = ExecOn::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.0 == "core1" && x.1.as_slice() == ["reboot", "-f"]);
+
+    let mut line = heapless::String::<32>::new();
+    let output = output.unwrap().to_args(&mut line);
+
+    assert_that!(output).is_ok();
+    assert_that!(line.as_str()).is_equal_to("core1 reboot -f");
+}
+
+#[test]
+fn it_should_suggest_closest_flag() {
+    use noshell::parser::{Error, lexer::Flag};
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        #[allow(unused)]
+        retries: Option<u32>,
+    }
+
+    let argv = &["--retires", "3"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_err_containing(noshell::Error::Parser(Error::MisspelledArgument(
+        Flag::Long("retries"),
+    )));
+}
+
+#[test]
+fn it_should_parse_abbreviated_flags() {
+    use noshell::parser::{Error, lexer::Flag};
+
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(abbrev)]
+    struct MyArgs {
+        verbose: Option<Option<u32>>,
+        version: Option<Option<u32>>,
+        retries: Option<u32>,
+    }
+
+    let argv = &["--verb", "--ret", "3"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.verbose == Some(None) && x.version.is_none() && x.retries == Some(3));
+
+    let argv = &["--ver"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_err().matches(|x| {
+        matches!(x, noshell::Error::Parser(Error::AmbiguousArgument(candidates))
+            if candidates.iter().eq([Flag::Long("verbose"), Flag::Long("version")]))
+    });
+}
+
+#[test]
+fn it_should_parse_intermixed_flags_and_positionals() {
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        port: u32,
+        verbose: Option<Option<u32>>,
+    }
+
+    let argv = &["--port", "2", "extra", "--verbose"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.port == 2 && x.verbose == Some(None));
+}
+
+#[test]
+fn it_should_stop_parsing_flags_at_first_positional_with_options_first() {
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(options_first)]
+    struct MyArgs {
+        port: u32,
+        verbose: Option<Option<u32>>,
+    }
+
+    let argv = &["--port", "2", "extra", "--verbose"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.port == 2 && x.verbose.is_none());
+}
+
+#[test]
+fn it_should_only_bind_value_with_equals_if_required() {
+    use heapless::String;
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        #[arg(long, require_equals, default_missing_value = "always")]
+        color: Option<Option<String<8>>>,
+    }
+
+    let argv = &["--color", "never"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.color == Some(Some("always".try_into().unwrap())));
+
+    let argv = &["--color=never"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.color == Some(Some("never".try_into().unwrap())));
+
+    let argv: &[&str] = &[];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().matches(|x| x.color.is_none());
+}
+
+#[test]
+fn it_should_parse_key_value_pairs_into_maps() {
+    use heapless::index_map::FnvIndexMap;
+    use heapless::{LinearMap, String};
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        set: FnvIndexMap<String<8>, u32, 4>,
+
+        #[arg(separator = ':')]
+        env: Option<LinearMap<String<8>, String<8>, 4>>,
+    }
+
+    let argv = &["--set", "a=1", "--env", "HOME:/root", "--set", "b=2"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().matches(|x| {
+        x.set
+            .iter()
+            .map(|(k, v)| (k.as_str(), *v))
+            .eq([("a", 1), ("b", 2)])
+            && x.env.as_ref().is_some_and(|env| env["HOME"] == "/root")
+    });
+
+    let argv = &["--set", "a=1", "a=2"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_err()
+        .is_equal_to(noshell::Error::Parser(noshell::parser::Error::DuplicateKey));
+
+    let argv = &["--set", "a"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_err()
+        .is_equal_to(noshell::Error::Parser(
+            noshell::parser::Error::MalformedPair,
+        ));
+}
+
+#[test]
+fn it_should_parse_delimited_values() {
+    use heapless::Vec;
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        #[arg(value_delimiter = ',')]
+        channels: Vec<u8, 8>,
+
+        #[arg(value_delimiter = ':')]
+        paths: Option<Vec<heapless::String<8>, 4>>,
+    }
+
+    let argv = &["--channels", "1,2", "--channels", "5"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.channels.as_slice() == [1, 2, 5] && x.paths.is_none());
+
+    let argv = &["--channels=1,x"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_err();
+}
+
+#[test]
+fn it_should_take_missing_args_from_settings() {
+    use heapless::Vec;
+    use noshell::parser::ValueSource;
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        #[arg(setting = "uart.baud")]
+        baud: u32,
+
+        #[arg(setting = "adc.channels", value_delimiter = ',')]
+        channels: Option<Vec<u8, 8>>,
+
+        #[arg(setting = "device.name")]
+        name: Option<heapless::String<8>>,
+
+        verbose: Option<bool>,
+    }
+
+    static SETTINGS: [(&str, &str); 3] = [
+        ("uart.baud", "9600"),
+        ("adc.channels", "1,2"),
+        ("device.name", "flash"),
+    ];
+
+    let argv = &["--baud", "115200"];
+    let output = MyArgs::try_parse_with(argv, &SETTINGS);
+
+    assert_that!(output).is_ok().matches(|(x, sources)| {
+        x.baud == 115200
+            && x.channels.as_deref() == Some(&[1, 2][..])
+            && x.name.as_deref() == Some("flash")
+            && x.verbose.is_none()
+            && sources.source_of("baud") == ValueSource::CommandLine
+            && sources.source_of("channels") == ValueSource::Provider
+            && sources.source_of("verbose") == ValueSource::Default
+    });
+
+    // Without any provider, the required argument is missing.
+    let output = MyArgs::try_parse_from(&[] as &[&str]);
+    assert_that!(output).is_err();
+}
+
+#[test]
+fn it_should_derive_with_crate_path() {
+    mod platform {
+        pub use noshell as shell;
+    }
+
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(crate = "platform::shell")]
+    struct MyArgs {
+        #[arg(short)]
+        count: Option<u32>,
+    }
+
+    let argv = &["-c", "3"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().matches(|x| x.count == Some(3));
+}
+
+#[test]
+fn it_should_parse_positional_args_of_tuple_struct() {
+    use heapless::Vec;
+
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(to_args)]
+    struct MyArgs(u32, Option<i32>, Vec<u8, 4>);
+
+    let argv = &["1", "-2", "3", "4"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.0 == 1 && x.1 == Some(-2) && x.2.as_slice() == [3, 4]);
+
+    let output = MyArgs::try_parse_from(&["1"]);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.0 == 1 && x.1.is_none() && x.2.is_empty());
+
+    let output = MyArgs::try_parse_from(&[] as &[&str]);
+
+    assert_that!(output)
+        .is_err()
+        .is_equal_to(noshell::Error::Parser(
+            noshell::parser::Error::MissingArgument,
+        ));
+
+    let mut line = heapless::String::<32>::new();
+    let output = MyArgs(1, Some(-2), Vec::from_slice(&[3]).unwrap()).to_args(&mut line);
+
+    assert_that!(output).is_ok();
+    assert_that!(line.as_str()).is_equal_to("1 -2 3");
+}
+
+#[test]
+fn it_should_parse_unit_struct_and_skipped_fields() {
+    #[derive(Debug, noshell::Parser)]
+    struct MyCommand;
+
+    assert_that!(MyCommand::try_parse_from(&[] as &[&str])).is_ok();
+    assert_that!(MyCommand::try_parse_from(&["--verbose"])).is_err();
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        count: u32,
+
+        #[arg(skip)]
+        calls: usize,
+
+        #[arg(skip = Some("state"))]
+        state: Option<&'static str>,
+    }
+
+    let argv = &["--count", "3"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output)
+        .is_ok()
+        .matches(|x| x.count == 3 && x.calls == 0 && x.state == Some("state"));
+
+    // Skipped fields are not flags.
+    let argv = &["--count", "3", "--calls", "1"];
+    assert_that!(MyArgs::try_parse_from(argv)).is_err();
+}
+
+#[test]
+fn it_should_parse_renamed_flags_and_aliases() {
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(rename_all = "kebab-case", to_args)]
+    struct MyArgs {
+        #[arg(alias = "retries", short_alias = 'r')]
+        max_retries: u32,
+
+        #[arg(long)]
+        dry_run: Option<Option<bool>>,
+    }
+
+    for argv in [
+        &["--max-retries", "3", "--dry-run"],
+        &["--retries", "3", "--dry-run"],
+        &["-r", "3", "--dry-run"],
+    ] {
+        let output = MyArgs::try_parse_from(argv);
+
+        assert_that!(output)
+            .is_ok()
+            .matches(|x| x.max_retries == 3 && x.dry_run == Some(None));
+    }
+
+    let argv = &["--max_retries", "3"];
+    assert_that!(MyArgs::try_parse_from(argv)).is_err();
+
+    let mut line = heapless::String::<32>::new();
+    let output = MyArgs::try_parse_from(&["-r", "3"]).map(|x| x.to_args(&mut line));
+
+    assert_that!(output).is_ok().is_ok();
+    assert_that!(line.as_str()).is_equal_to("--max-retries=3");
+}
+
+#[test]
+fn it_should_write_args_back_to_command_line() {
+    use heapless::index_map::FnvIndexMap;
+    use heapless::{String, Vec};
+
+    #[derive(Debug, PartialEq, noshell::Parser)]
+    #[noshell(to_args)]
+    struct MyArgs<'a> {
+        #[arg(short = 'n')]
+        count: i32,
+        name: Option<String<16>>,
+        verbose: Option<Option<u32>>,
+        color: Option<Option<String<8>>>,
+        #[arg(long = "chan", value_delimiter = ',')]
+        channels: Vec<u8, 4>,
+        set: Option<FnvIndexMap<String<4>, i32, 4>>,
+
+        #[arg(trailing_var_arg)]
+        command: Option<Vec<&'a str, 8>>,
+    }
+
+    let args = MyArgs {
+        count: -3,
+        name: Some("it's a name".try_into().unwrap()),
+        verbose: Some(None),
+        color: Some(Some(String::new())),
+        channels: Vec::from_slice(&[1, 2]).unwrap(),
+        set: Some(FnvIndexMap::from_iter([
+            ("a".try_into().unwrap(), -1),
+            ("b".try_into().unwrap(), 2),
+        ])),
+        command: Some(Vec::from_slice(&["echo", "--not-a-flag", "x y"]).unwrap()),
+    };
+
+    let mut line: String<256> = String::new();
+    assert_that!(args.to_args(&mut line)).is_ok();
+    assert_that!(line.as_str()).is_equal_to(
+        "--verbose -n -3 \"--name=it's a name\" --color= --chan=1 --chan=2 --set=a=-1 \
+         --set=b=2 echo --not-a-flag 'x y'",
+    );
+
+    let words: Vec<&str, 16> = noshell::cmdline::lexer::split(&line)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let output = MyArgs::try_parse_from(&words);
+
+    assert_that!(output).is_ok().is_equal_to(&args);
+}
+
+#[test]
+fn it_should_not_write_args_that_cannot_be_parsed_back() {
+    use heapless::{String, Vec};
+
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(to_args)]
+    struct MyArgs<'a> {
+        #[arg(short = 'n')]
+        name: String<8>,
+
+        #[arg(trailing_var_arg)]
+        command: Vec<&'a str, 8>,
+    }
+
+    let args = MyArgs {
+        name: "--name".try_into().unwrap(),
+        command: Vec::new(),
+    };
+
+    let mut line: String<64> = String::new();
+    assert_that!(args.to_args(&mut line)).is_err();
+
+    // Trailing words which look like flags come after the end of options.
+    let args = MyArgs {
+        name: "x".try_into().unwrap(),
+        command: Vec::from_slice(&["--exec"]).unwrap(),
+    };
+
+    let mut line: String<64> = String::new();
+    assert_that!(args.to_args(&mut line)).is_ok();
+    assert_that!(line.as_str()).is_equal_to("-n x -- --exec");
+
+    let words: Vec<&str, 8> = noshell::cmdline::lexer::split(&line)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let output = MyArgs::try_parse_from(&words).unwrap();
+    assert_that!(output.command.as_slice()).is_equal_to(["--exec"].as_slice());
+}
+
+#[test]
+fn it_should_write_many_values_back_to_command_line() {
+    use heapless::{String, Vec};
+
+    fn round_trip<T, F>(args: &T, to_args: F, parse: fn(&[&str]) -> Option<T>) -> Option<T>
+    where
+        F: Fn(&T, &mut String<128>) -> core::fmt::Result,
+    {
+        let mut line = String::new();
+        to_args(args, &mut line).ok()?;
+
+        let words: Vec<&str, 16> = noshell::cmdline::lexer::split(&line)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        parse(&words)
+    }
+
+    // Short flags with many values are closed by the following flags.
+    #[derive(Debug, PartialEq, noshell::Parser)]
+    #[noshell(to_args)]
+    struct ShortArgs<'a> {
+        #[arg(short = 'c')]
+        channels: Vec<u8, 4>,
+
+        #[arg(short = 'p')]
+        ports: Option<Vec<u8, 4>>,
+
+        #[arg(short = 'e', trailing_var_arg)]
+        exec: Vec<&'a str, 4>,
+    }
+
+    let args = ShortArgs {
+        channels: Vec::from_slice(&[1, 2]).unwrap(),
+        ports: Some(Vec::from_slice(&[3]).unwrap()),
+        exec: Vec::from_slice(&["cmd", "-c", "arg"]).unwrap(),
+    };
+
+    let mut line: String<64> = String::new();
+    assert_that!(args.to_args(&mut line)).is_ok();
+    assert_that!(line.as_str()).is_equal_to("-c 1 2 -p 3 -e cmd -c arg");
+
+    let words: Vec<&str, 16> = noshell::cmdline::lexer::split(&line)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_that!(ShortArgs::try_parse_from(&words))
+        .is_ok()
+        .is_equal_to(&args);
+
+    // Long flags with many values, followed by positional trailing words.
+    #[derive(Debug, PartialEq, noshell::Parser)]
+    #[noshell(to_args)]
+    struct LongArgs {
+        #[arg(short = 'c', long)]
+        channels: Vec<u8, 4>,
+
+        #[arg(trailing_var_arg)]
+        rest: Vec<String<8>, 4>,
+    }
+
+    let args = LongArgs {
+        channels: Vec::from_slice(&[1, 2]).unwrap(),
+        rest: Vec::from_iter(["cmd".try_into().unwrap(), "arg".try_into().unwrap()]),
+    };
+
+    let output = round_trip(&args, LongArgs::to_args, |x| {
+        LongArgs::try_parse_from(x).ok()
+    });
+    assert_that!(output).is_some().is_equal_to(&args);
+
+    // Positional arguments and trailing words.
+    #[derive(Debug, PartialEq, noshell::Parser)]
+    #[noshell(to_args)]
+    struct Repeat(u32, Vec<u8, 4>);
+
+    let args = Repeat(5, Vec::from_slice(&[1, 2]).unwrap());
+    let output = round_trip(&args, Repeat::to_args, |x| Repeat::try_parse_from(x).ok());
+    assert_that!(output).is_some().is_equal_to(&args);
+
+    // Short flags with many values are closed by the end of options before positional words.
+    #[derive(Debug, PartialEq, noshell::Parser)]
+    #[noshell(to_args)]
+    struct ShortOnlyArgs<'a> {
+        #[arg(short = 'c')]
+        channels: Vec<u8, 4>,
+
+        #[arg(trailing_var_arg)]
+        rest: Vec<&'a str, 4>,
+    }
+
+    let args = ShortOnlyArgs {
+        channels: Vec::from_slice(&[1, 2]).unwrap(),
+        rest: Vec::from_slice(&["cmd", "arg"]).unwrap(),
+    };
+
+    let mut line: String<64> = String::new();
+    assert_that!(args.to_args(&mut line)).is_ok();
+    assert_that!(line.as_str()).is_equal_to("-c 1 2 -- cmd arg");
+
+    let words: Vec<&str, 16> = noshell::cmdline::lexer::split(&line)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_that!(ShortOnlyArgs::try_parse_from(&words))
+        .is_ok()
+        .is_equal_to(&args);
+}
+
+#[test]
+fn it_should_write_positional_args_after_flags_without_value() {
+    use heapless::{String, Vec};
+
+    #[derive(Debug, PartialEq, noshell::Parser)]
+    #[noshell(to_args)]
+    struct MyArgs {
+        #[arg(long)]
+        color: Option<Option<String<8>>>,
+
+        #[arg(trailing_var_arg)]
+        rest: Vec<String<8>, 4>,
+    }
+
+    fn round_trip(args: &MyArgs) -> (String<64>, MyArgs) {
+        let mut line = String::new();
+        args.to_args(&mut line).unwrap();
+
+        let words: Vec<&str, 16> = noshell::cmdline::lexer::split(&line)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        (line.clone(), MyArgs::try_parse_from(&words).unwrap())
+    }
+
+    // The flag without value would take the trailing words.
+    let args = MyArgs {
+        color: Some(None),
+        rest: Vec::from_iter(["1".try_into().unwrap()]),
+    };
+
+    let (line, output) = round_trip(&args);
+    assert_that!(line.as_str()).is_equal_to("--color -- 1");
+    assert_that!(output).is_equal_to(&args);
+
+    // Negative numbers are not flags, but other words starting with a dash are.
+    let args = MyArgs {
+        color: None,
+        rest: Vec::from_iter(["-5".try_into().unwrap(), "-x".try_into().unwrap()]),
+    };
+
+    let (line, output) = round_trip(&args);
+    assert_that!(line.as_str()).is_equal_to("-5 -x");
+    assert_that!(output).is_equal_to(&args);
+
+    let args = MyArgs {
+        color: Some(Some("auto".try_into().unwrap())),
+        rest: Vec::from_iter(["-x".try_into().unwrap(), "-5".try_into().unwrap()]),
+    };
+
+    let (line, output) = round_trip(&args);
+    assert_that!(line.as_str()).is_equal_to("--color=auto -- -x -5");
+    assert_that!(output).is_equal_to(&args);
+}
+
+#[test]
+fn it_should_parse_args_with_qualified_types() {
+    #[derive(Debug, PartialEq, Eq, noshell::Parser)]
+    struct MyArgs {
+        value: core::option::Option<u32>,
+        channels: heapless::Vec<u8, 4>,
+    }
+
+    let argv = &["--channels", "1", "2", "--value", "42"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().is_equal_to(&MyArgs {
+        value: Some(42),
+        channels: heapless::Vec::from_slice(&[1, 2]).unwrap(),
+    });
+}
+
+#[test]
+fn it_should_parse_args_with_array_type() {
+    #[derive(Debug, PartialEq, Eq, noshell::Parser)]
+    #[noshell(to_args)]
+    struct MyArgs {
+        #[arg(value_delimiter = '.')]
+        address: [u8; 4],
+        mask: Option<[u8; 2]>,
+    }
+
+    let argv = &["--address", "192.168.1.2"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().is_equal_to(&MyArgs {
+        address: [192, 168, 1, 2],
+        mask: None,
+    });
+
+    let argv = &["--address", "192.168.1", "--mask", "1", "2"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_err();
+
+    let args = MyArgs {
+        address: [10, 0, 0, 1],
+        mask: Some([255, 0]),
+    };
+
+    let mut line: heapless::String<128> = heapless::String::new();
+    assert_that!(args.to_args(&mut line)).is_ok();
+    assert_that!(line.as_str())
+        .is_equal_to("--address=10 --address=0 --address=0 --address=1 --mask=255 --mask=0");
+}
+
+#[test]
+fn it_should_parse_args_with_type_aliases() {
+    type Channels = heapless::Vec<u8, 4>;
+    type Retries = Option<u32>;
+
+    #[derive(Debug, PartialEq, Eq, noshell::Parser)]
+    struct MyArgs {
+        #[arg(kind = "vec")]
+        channels: Channels,
+
+        #[arg(kind = "option")]
+        retries: Retries,
+    }
+
+    let argv = &["--channels", "1", "2"];
+    let output = MyArgs::try_parse_from(argv);
+
+    assert_that!(output).is_ok().is_equal_to(&MyArgs {
+        channels: heapless::Vec::from_slice(&[1, 2]).unwrap(),
+        retries: None,
+    });
+}
+
+#[test]
+fn it_should_parse_args_whose_type_is_not_comparable() {
+    // Only `FromStr` is required, unless the struct asks for `try_update_from`.
+    #[derive(Debug)]
+    struct Level(u8);
+
+    impl core::str::FromStr for Level {
+        type Err = core::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.parse().map(Level)
+        }
+    }
+
+    #[derive(Debug, noshell::Parser)]
+    struct MyArgs {
+        level: Level,
+    }
+
+    let args = MyArgs::try_parse_from(["--level", "3"]);
+    assert_that!(args.map(|x| x.level.0)).is_ok().is_equal_to(3);
+}
+
+#[test]
+fn it_should_update_args_given_on_the_command_line() {
+    use heapless::Vec;
+
+    #[derive(Debug, PartialEq, Eq, noshell::Parser)]
+    #[noshell(update)]
+    struct MyArgs {
+        #[arg(short)]
+        baud: u32,
+        parity: Option<bool>,
+        channels: Vec<u8, 4>,
+    }
+
+    let mut args = MyArgs {
+        baud: 9600,
+        parity: Some(true),
+        channels: Vec::from_slice(&[1]).unwrap(),
+    };
+
+    let changed = args.try_update_from(["--channels", "2", "3", "-b", "115200"]);
+
+    assert_that!(changed)
+        .is_ok()
+        .matches(|x| x.iter().eq(["baud", "channels"]) && !x.is_changed("parity"));
+    assert_that!(args).is_equal_to(MyArgs {
+        baud: 115200,
+        parity: Some(true),
+        channels: Vec::from_slice(&[2, 3]).unwrap(),
+    });
+
+    // Nothing is changed if any value is invalid.
+    let changed = args.try_update_from(["--parity", "false", "-b", "fast"]);
+
+    assert_that!(changed).is_err();
+    assert_that!(args.parity).is_equal_to(Some(true));
+
+    let changed = args.try_update_from::<[&str; 0], &str>([]);
+    assert_that!(changed).is_ok().matches(|x| x.is_empty());
+    // A field set to its current value is not changed.
+    let changed = args.try_update_from(["-b", "115200", "--parity", "false"]);
+
+    assert_that!(changed)
+        .is_ok()
+        .matches(|x| x.iter().eq(["parity"]) && !x.is_changed("baud"));
+    assert_that!(args.baud).is_equal_to(115200);
+}
+
+#[test]
+fn it_should_leave_out_cfg_gated_args() {
+    #[derive(Debug, PartialEq, Eq, noshell::Parser)]
+    struct MyArgs {
+        retries: Option<u32>,
+
+        #[cfg(not(test))]
+        factory: Option<u32>,
+
+        #[cfg(test)]
+        #[cfg_attr(test, arg(short))]
+        verbose: Option<u32>,
+    }
+
+    let output = MyArgs::try_parse_from(["--retries", "2", "-v", "1"]);
+    assert_that!(output).is_ok().is_equal_to(MyArgs {
+        retries: Some(2),
+        verbose: Some(1),
+    });
+
+    assert_that!(MyArgs::try_parse_from(["--factory", "1"])).is_err();
+}
+
+#[test]
+fn it_should_parse_hidden_and_deprecated_args() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static DEPRECATED: AtomicUsize = AtomicUsize::new(0);
+
+    fn warn(id: &str, note: &str) {
+        assert_eq!(("baud_rate", "use --baud"), (id, note));
+        DEPRECATED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[derive(Debug, PartialEq, Eq, noshell::Parser)]
+    #[noshell(deprecation_hook = warn)]
+    struct MyArgs {
+        baud: Option<u32>,
+
+        #[arg(deprecated = "use --baud")]
+        baud_rate: Option<u32>,
+
+        #[arg(hide)]
+        factory_reset: Option<bool>,
+    }
+
+    let output = MyArgs::try_parse_from(["--baud_rate", "9600", "--factory_reset", "true"]);
+    assert_that!(output).is_ok().is_equal_to(MyArgs {
+        baud: None,
+        baud_rate: Some(9600),
+        factory_reset: Some(true),
+    });
+    assert_that!(DEPRECATED.load(Ordering::Relaxed)).is_equal_to(1);
+
+    let info = MyArgs::ARGS;
+    assert_that!(info[1].deprecated()).is_equal_to(Some("use --baud"));
+    assert_that!(info[2].is_hidden()).is_true();
+    assert_that!(info.iter().filter(|x| x.is_hidden()).count()).is_equal_to(1);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn it_should_document_args_from_the_struct() {
+    use noshell::parser::CommandInfo;
+
+    /// Configure the modem.
+    #[derive(Debug, noshell::Parser)]
+    #[allow(dead_code)]
+    struct MyArgs {
+        /// Baud rate of the link.
+        baud: u32,
+
+        #[arg(hide)]
+        factory: Option<bool>,
+    }
+
+    let cmd = CommandInfo::new("modem", MyArgs::ARGS).with_about(MyArgs::ABOUT);
+    let output = noshell::docs::markdown(&cmd);
+
+    assert_that!(output.as_str()).contains("Configure the modem.");
+    assert_that!(output.as_str()).contains("- `--baud <BAUD>` (required): Baud rate of the link.");
+    assert_that!(output.contains("factory")).is_false();
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn it_should_complete_all_the_flags_of_the_struct() {
+    use noshell::completions::{Shell, generate};
+    use noshell::parser::CommandInfo;
+
+    #[derive(Debug, PartialEq, noshell::ValueEnum)]
+    enum Parity {
+        None,
+        Even,
+        MarkSpace,
+    }
+
+    #[derive(Debug, noshell::Parser)]
+    #[noshell(to_args)]
+    #[allow(dead_code)]
+    struct MyArgs {
+        #[arg(short, long, alias = "speed")]
+        baud: Option<u32>,
+
+        #[arg(hide)]
+        factory: Option<bool>,
+
+        #[arg(value_enum)]
+        parity: Option<Parity>,
+    }
+
+    let cmd = CommandInfo::new("modem", MyArgs::ARGS).with_lookup_table(MyArgs::lookup_table());
+    let output = generate(Shell::Bash, &cmd);
+
+    assert_that!(output.as_str()).contains("'modem') opts='-b --baud --speed --parity' ;;");
+    assert_that!(output.as_str()).contains("'modem:--parity') COMPREPLY=($(compgen -W 'none even mark-space' -- \"${cur}\")); return ;;");
+
+    let args = MyArgs::try_parse_from(["--parity", "mark-space"]).unwrap();
+    assert_that!(args.parity).is_equal_to(Some(Parity::MarkSpace));
+    assert_that!(MyArgs::try_parse_from(["--parity", "odd"])).is_err();
+
+    // A misspelled value is reported along with the closest possible one.
+    let expected = noshell::Error::Parser(noshell::parser::Error::InvalidValue(Some("even")));
+    assert_that!(MyArgs::try_parse_from(["--parity", "evn"]).map(|_| ()))
+        .is_err_containing(&expected);
+    assert_that!("nne".parse::<Parity>())
+        .is_err_containing(noshell::parser::Error::InvalidValue(Some("none")));
+
+    let mut out = String::new();
+    args.to_args(&mut out).unwrap();
+    assert_that!(out.as_str()).is_equal_to("--parity=mark-space");
+}
+
+#[test]
+fn it_should_parse_byte_words() {
+    use noshell::parser::Bytes;
+
+    #[derive(Debug, PartialEq, Eq, noshell::Parser)]
+    struct MyArgs {
+        address: u16,
+        length: Option<u8>,
+    }
+
+    let argv: [&[u8]; 3] = [b"--address", b"4096", b"--length=\xff"];
+    assert_that!(MyArgs::try_parse_from::<_, Bytes<'_>>(&argv[..2]))
+        .is_ok()
+        .is_equal_to(MyArgs {
+            address: 4096,
+            length: None,
+        });
+    assert_that!(MyArgs::try_parse_from::<_, Bytes<'_>>(argv)).is_err();
+}